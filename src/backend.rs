@@ -0,0 +1,250 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use minifb::{Key, Window, WindowOptions};
+
+use crate::{Pixel, Rect};
+
+/// Presentation target for a `Screen`: receives finished frames, reports input
+/// and tells the render loop whether it should keep going.
+pub trait Backend {
+    fn present(
+        &mut self,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+    ) -> Result<(), BackendError>;
+
+    /// Presents a frame of which only `regions` changed since the previous
+    /// one. Backends that cannot update part of their target present it all.
+    fn present_regions(
+        &mut self,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+        _regions: &[Rect],
+    ) -> Result<(), BackendError> {
+        self.present(buffer, width, height)
+    }
+
+    /// Processes pending input without presenting a new frame.
+    fn poll_events(&mut self) {}
+
+    fn is_open(&self) -> bool;
+
+    fn is_key_down(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn set_target_fps(&mut self, _fps: usize) {}
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    Window(minifb::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Window(e) => write!(f, "window error: {}", e),
+            BackendError::Io(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl From<minifb::Error> for BackendError {
+    fn from(e: minifb::Error) -> Self {
+        BackendError::Window(e)
+    }
+}
+
+impl From<io::Error> for BackendError {
+    fn from(e: io::Error) -> Self {
+        BackendError::Io(e)
+    }
+}
+
+/// Presents frames in a minifb window.
+pub struct MinifbBackend {
+    pub window: Window,
+}
+
+impl MinifbBackend {
+    pub fn new(
+        width: usize,
+        height: usize,
+        window_name: &str,
+        opts: WindowOptions,
+    ) -> Result<Self, BackendError> {
+        let mut window = Window::new(window_name, width, height, opts)?;
+        window.set_target_fps(60);
+        Ok(MinifbBackend { window })
+    }
+}
+
+impl Backend for MinifbBackend {
+    fn present(
+        &mut self,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+    ) -> Result<(), BackendError> {
+        self.window.update_with_buffer(buffer, width, height)?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self) {
+        self.window.update();
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.window.is_key_down(key)
+    }
+
+    fn set_target_fps(&mut self, fps: usize) {
+        self.window.set_target_fps(fps);
+    }
+}
+
+/// Keeps every presented frame in memory. Useful for tests: keys can be
+/// pressed by hand and the backend closes itself after `max_frames`.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    pub frames: Vec<Vec<Pixel>>,
+    /// Changed areas of each frame in `frames`; the whole frame when it was
+    /// presented without regions.
+    pub regions: Vec<Vec<Rect>>,
+    max_frames: Option<usize>,
+    keys_down: Vec<Key>,
+    closed: bool,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        MemoryBackend::default()
+    }
+
+    pub fn with_max_frames(max_frames: usize) -> Self {
+        MemoryBackend {
+            max_frames: Some(max_frames),
+            ..MemoryBackend::default()
+        }
+    }
+
+    pub fn last_frame(&self) -> Option<&[Pixel]> {
+        self.frames.last().map(|frame| frame.as_slice())
+    }
+
+    pub fn press(&mut self, key: Key) {
+        if !self.keys_down.contains(&key) {
+            self.keys_down.push(key);
+        }
+    }
+
+    pub fn release(&mut self, key: Key) {
+        self.keys_down.retain(|k| *k != key);
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn present(
+        &mut self,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+    ) -> Result<(), BackendError> {
+        let whole = Rect::new(0, 0, width, height);
+        self.present_regions(buffer, width, height, &[whole])
+    }
+
+    fn present_regions(
+        &mut self,
+        buffer: &[Pixel],
+        _width: usize,
+        _height: usize,
+        regions: &[Rect],
+    ) -> Result<(), BackendError> {
+        self.frames.push(buffer.to_vec());
+        self.regions.push(regions.to_vec());
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        !self.closed && self.max_frames.is_none_or(|max| self.frames.len() < max)
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+}
+
+/// Writes every presented frame to `<dir>/<prefix>_<index>.ppm` for offline
+/// rendering.
+#[derive(Debug)]
+pub struct FileSequenceBackend {
+    dir: PathBuf,
+    prefix: String,
+    frame: usize,
+    max_frames: Option<usize>,
+}
+
+impl FileSequenceBackend {
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str) -> Self {
+        FileSequenceBackend {
+            dir: dir.into(),
+            prefix: prefix.to_string(),
+            frame: 0,
+            max_frames: None,
+        }
+    }
+
+    pub fn with_max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.frame
+    }
+
+    pub fn frame_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}_{:05}.ppm", self.prefix, index))
+    }
+}
+
+impl Backend for FileSequenceBackend {
+    fn present(
+        &mut self,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+    ) -> Result<(), BackendError> {
+        let file = File::create(self.frame_path(self.frame))?;
+        let mut out = BufWriter::new(file);
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        for pixel in buffer {
+            out.write_all(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8])?;
+        }
+        out.flush()?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.max_frames.is_none_or(|max| self.frame < max)
+    }
+}
@@ -0,0 +1,136 @@
+use nalgebra::Vector2;
+
+use crate::transform::Placement;
+use crate::{BlendMode, Canvas, Image, Pixel, Rect, Surface, color};
+
+/// How the pixels of a blitted image are combined with the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlitMode {
+    /// Copies pixels as fully opaque, ignoring their alpha.
+    Opaque,
+    /// Skips pixels whose color matches the key, ignoring alpha, and copies
+    /// the rest as opaque.
+    ColorKey(Pixel),
+    /// Blends pixels using their alpha.
+    Alpha,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlitOptions {
+    pub mode: BlitMode,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Multiplied into every pixel, alpha included.
+    pub tint: Option<Pixel>,
+    /// Draws the current stroke around the destination, like `draw_buffer`.
+    pub outline: bool,
+}
+
+impl Default for BlitOptions {
+    fn default() -> Self {
+        BlitOptions {
+            mode: BlitMode::Alpha,
+            flip_x: false,
+            flip_y: false,
+            tint: None,
+            outline: false,
+        }
+    }
+}
+
+impl Canvas {
+    /// Copies the `src` part of `image` with its top-left corner at `dst`.
+    /// Like `draw_buffer`, the image is placed at the transformed position of
+    /// `dst` but not rotated or scaled.
+    pub fn blit(&mut self, image: &Image, src: Rect, dst: Vector2<isize>, options: &BlitOptions) {
+        self.blit_pixels(&image.pixels, image.width, image.height, src, dst, options);
+    }
+
+    /// Draws a whole `layer` with its top-left corner at `pos`, its alpha
+    /// scaled by `opacity` (`0.0..=1.0`).
+    pub fn composite(
+        &mut self,
+        layer: &Surface,
+        pos: Vector2<isize>,
+        opacity: f32,
+        mode: BlendMode,
+    ) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let options = BlitOptions {
+            tint: (opacity < 1.0)
+                .then(|| color::rgba(255, 255, 255, (opacity * 255.0).round() as u8)),
+            ..BlitOptions::default()
+        };
+        let (width, height) = (layer.get_width(), layer.get_height());
+        let src = Rect::new(0, 0, width, height);
+        let previous = std::mem::replace(&mut self.blend_mode, mode);
+        self.blit_pixels(&layer.buffer, width, height, src, pos, &options);
+        self.blend_mode = previous;
+    }
+
+    pub(crate) fn blit_pixels(
+        &mut self,
+        pixels: &[Pixel],
+        width: usize,
+        height: usize,
+        src: Rect,
+        dst: Vector2<isize>,
+        options: &BlitOptions,
+    ) {
+        // pixels of `src` outside the image are skipped without moving the
+        // rest; flipped, the trimmed side lands on the other end
+        let clipped = src.intersect(&Rect::new(0, 0, width, height));
+        let shift = Vector2::new(
+            if options.flip_x {
+                src.right() - clipped.right()
+            } else {
+                clipped.x - src.x
+            },
+            if options.flip_y {
+                src.bottom() - clipped.bottom()
+            } else {
+                clipped.y - src.y
+            },
+        );
+        let (src, dst) = (clipped, dst + shift);
+        let dst = match self.placement() {
+            Placement::Offset(offset) => dst + offset,
+            Placement::General => {
+                let mapped = self.transform_point(dst.cast());
+                Vector2::new(mapped.x.round() as isize, mapped.y.round() as isize)
+            }
+        };
+        let area = Rect::new(dst.x, dst.y, src.width, src.height).intersect(&self.clip);
+        for y in area.y..area.bottom() {
+            let dy = (y - dst.y) as usize;
+            let sy = if options.flip_y {
+                src.height - 1 - dy
+            } else {
+                dy
+            };
+            for x in area.x..area.right() {
+                let dx = (x - dst.x) as usize;
+                let sx = if options.flip_x {
+                    src.width - 1 - dx
+                } else {
+                    dx
+                };
+                let index = (src.y as usize + sy) * width + src.x as usize + sx;
+                let mut pixel = pixels[index];
+                match options.mode {
+                    BlitMode::Opaque => pixel &= 0xFFFFFF,
+                    BlitMode::ColorKey(key) if pixel & 0xFFFFFF == key & 0xFFFFFF => continue,
+                    BlitMode::ColorKey(_) => pixel &= 0xFFFFFF,
+                    BlitMode::Alpha => {}
+                }
+                if let Some(tint) = options.tint {
+                    pixel = color::multiply(pixel, tint);
+                }
+                self.blend_pixel(Vector2::new(x, y), pixel);
+            }
+        }
+        if options.outline {
+            self.rect_at(dst, src.width, src.height, true);
+        }
+    }
+}
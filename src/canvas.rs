@@ -0,0 +1,628 @@
+use std::f32::consts::TAU;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use nalgebra::{Matrix3, Vector2};
+
+use crate::blit::BlitOptions;
+use crate::dash::{circle_outline_position, rect_outline_position};
+use crate::dirty::DirtyTiles;
+use crate::font::get_font_5x7;
+use crate::transform::Placement;
+use crate::{
+    BlendMode, FillRule, LineCap, LineJoin, Paint, Pixel, Rect, Stroke, StrokeType, color, image,
+    raster, shapes,
+};
+
+/// Window-free drawing target: owns the pixel buffer and the stroke/fill state
+/// used by every primitive. `Screen` is a `Canvas` plus a window.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pub buffer: Vec<Pixel>,
+    pub(crate) stroke: Stroke,
+    pub(crate) fill: Paint,
+    pub(crate) fill_rule: FillRule,
+    pub(crate) antialias: bool,
+    pub(crate) blend_mode: BlendMode,
+    pub(crate) clip: Rect,
+    clip_stack: Vec<Rect>,
+    pub(crate) transform: Matrix3<f32>,
+    pub(crate) transform_stack: Vec<Matrix3<f32>>,
+    pub(crate) threads: usize,
+    pub(crate) dirty: DirtyTiles,
+}
+
+/// Offscreen drawing target. Draw into it like into the screen, then put it
+/// on the screen with `composite`, e.g. to cache a background or a chart.
+pub type Surface = Canvas;
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        let buffer = vec![0x000000; width * height]; // Initialize with black color
+
+        let stroke = Stroke::new(0xFFFFFF, 1, StrokeType::Center); // Default stroke: white, 1 px
+
+        let fill = Paint::Solid(0x000000); // Default fill color (black)
+
+        Canvas {
+            width,
+            height,
+            buffer,
+            stroke,
+            fill,
+            fill_rule: FillRule::NonZero,
+            antialias: false,
+            blend_mode: BlendMode::Normal,
+            clip: Rect::new(0, 0, width, height),
+            clip_stack: Vec::new(),
+            transform: Matrix3::identity(),
+            transform_stack: Vec::new(),
+            threads: 1,
+            dirty: DirtyTiles::new(width, height),
+        }
+    }
+
+    pub fn stroke_color(&mut self, color: Pixel) {
+        self.stroke.color = color;
+    }
+    pub fn stroke_width(&mut self, width: usize) {
+        self.stroke.width = width;
+    }
+    pub fn stroke_type(&mut self, s_type: StrokeType) {
+        self.stroke.stroke_type = s_type;
+    }
+    pub fn line_join(&mut self, join: LineJoin) {
+        self.stroke.line_join = join;
+    }
+    pub fn line_cap(&mut self, cap: LineCap) {
+        self.stroke.line_cap = cap;
+    }
+    /// Longest miter, as a multiple of the stroke width, before a
+    /// `LineJoin::Miter` corner is beveled instead.
+    pub fn miter_limit(&mut self, limit: f32) {
+        self.stroke.miter_limit = limit;
+    }
+    /// Dashes lines and outlines with alternating on/off lengths, starting
+    /// `offset` into the pattern. An empty pattern draws solid strokes.
+    pub fn stroke_dash(&mut self, pattern: &[f32], offset: f32) {
+        self.stroke.dash = pattern.to_vec();
+        self.stroke.dash_offset = offset;
+    }
+
+    pub fn fill(&mut self, color: Pixel) {
+        self.fill = Paint::Solid(color);
+    }
+
+    /// How everything drawn from now on combines with the buffer.
+    pub fn blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    /// Smooths the edges of lines and circles.
+    pub fn antialias(&mut self, enabled: bool) {
+        self.antialias = enabled;
+    }
+
+    /// Restricts drawing to `rect`, intersected with the current clip region.
+    /// Every `push_clip` must be matched by a `pop_clip`.
+    pub fn push_clip(&mut self, rect: Rect) {
+        self.clip_stack.push(self.clip);
+        self.clip = self.clip.intersect(&rect);
+    }
+
+    /// Restores the clip region active before the last `push_clip`.
+    pub fn pop_clip(&mut self) {
+        if let Some(clip) = self.clip_stack.pop() {
+            self.clip = clip;
+        }
+    }
+
+    pub fn clip_rect(&self) -> Rect {
+        self.clip
+    }
+
+    /// Draws shapes in screen coordinates; where shapes overlap only the last
+    /// one in `shapes` is drawn. Each shape is only asked about the pixels in
+    /// its bounding box.
+    pub fn draw_shapes(&mut self, shapes: &[Box<dyn shapes::Shape>]) {
+        let clip = self.clip;
+        // pixels already taken by a shape higher up
+        let mut covered = vec![false; clip.width * clip.height];
+        for shape in shapes.iter().rev() {
+            let area = shape
+                .bounding_box()
+                .map_or(clip, |bounds| bounds.intersect(&clip));
+            for y in area.y..area.bottom() {
+                for x in area.x..area.right() {
+                    let index = (y - clip.y) as usize * clip.width + (x - clip.x) as usize;
+                    if covered[index] {
+                        continue;
+                    }
+                    let coordinates = Vector2::new(x, y);
+                    if let Some(color) = shape.is_in_shape(coordinates) {
+                        covered[index] = true;
+                        self.blend_pixel(coordinates, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // FIX: check srtoke width
+    pub fn circle(&mut self, center: Vector2<isize>, radius: usize) {
+        let (inner_rad, outer_rad) = self.ring_radii(radius);
+        match self.placement() {
+            Placement::Offset(offset) => self.circle_at(center + offset, inner_rad, outer_rad),
+            Placement::General => self.circle_transformed(center, inner_rad, outer_rad),
+        }
+    }
+
+    pub(crate) fn ring_radii(&self, radius: usize) -> (usize, usize) {
+        match self.stroke.stroke_type {
+            StrokeType::Inner => (radius.saturating_sub(self.stroke.width), radius),
+            StrokeType::Outer => (radius, radius + self.stroke.width),
+            StrokeType::Center => (
+                radius.saturating_sub(self.stroke.width / 2),
+                radius + self.stroke.width / 2,
+            ),
+        }
+    }
+
+    fn circle_at(&mut self, center: Vector2<isize>, inner_rad: usize, outer_rad: usize) {
+        if self.antialias {
+            self.circle_antialiased(center, inner_rad as f32, outer_rad as f32);
+            return;
+        }
+        let (inner_rad, outer_rad) = (inner_rad as isize, outer_rad as isize);
+        let inner_sq = inner_rad * inner_rad;
+        let outer_sq = outer_rad * outer_rad;
+        let mid_rad = (inner_rad + outer_rad) as f32 / 2.0;
+
+        let fill = self.fill_sampler();
+        let dashed = self.stroke.is_dashed();
+        let (x_range, y_range) = self.clipped_offsets(center, outer_rad);
+        for y in y_range {
+            for x in x_range.clone() {
+                let point = Vector2::new(x, y);
+                let dist_sq = point.dot(&point);
+                if dist_sq <= outer_sq {
+                    let canvas_point = center + point;
+                    if dist_sq > inner_sq {
+                        if !dashed
+                            || self
+                                .stroke
+                                .dash_on(circle_outline_position(point.cast(), mid_rad))
+                        {
+                            self.blend_pixel(canvas_point, self.stroke.color); // Draw the stroke
+                        }
+                    } else {
+                        let color = fill.color_at(canvas_point.x, canvas_point.y);
+                        self.blend_pixel(canvas_point, color); // Fill the circle
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rotated or scaled circles become ellipses, drawn as polygons.
+    fn circle_transformed(&mut self, center: Vector2<isize>, inner_rad: usize, outer_rad: usize) {
+        let center = center.cast::<f32>().add_scalar(0.5);
+        let outer = self.arc_points(center, Vector2::repeat(outer_rad as f32 + 0.5), 0.0, TAU);
+        let inner = self.arc_points(center, Vector2::repeat(inner_rad as f32 + 0.5), 0.0, TAU);
+        if outer_rad > inner_rad {
+            let stroke = self.stroke.clone();
+            let dashed = stroke.is_dashed();
+            let mid_rad = (inner_rad + outer_rad) as f32 / 2.0 + 0.5;
+            self.fill_contours_masked(
+                &[outer, inner.clone()],
+                &Paint::Solid(stroke.color),
+                |local| !dashed || stroke.dash_on(circle_outline_position(local - center, mid_rad)),
+            );
+        }
+        self.fill_contours(&[inner], &self.fill.clone());
+    }
+
+    fn circle_antialiased(&mut self, center: Vector2<isize>, inner_rad: f32, outer_rad: f32) {
+        let reach = outer_rad.ceil() as isize + 1;
+        let mid_rad = (inner_rad + outer_rad) / 2.0;
+        let fill = self.fill_sampler();
+        let dashed = self.stroke.is_dashed();
+        let (x_range, y_range) = self.clipped_offsets(center, reach);
+        for y in y_range {
+            for x in x_range.clone() {
+                let distance = ((x * x + y * y) as f32).sqrt();
+                let coverage = raster::edge_coverage(distance - outer_rad);
+                if coverage > 0.0 {
+                    let fill_coverage = raster::edge_coverage(distance - inner_rad);
+                    let (px, py) = (center.x + x, center.y + y);
+                    let offset = Vector2::new(x as f32, y as f32);
+                    if !dashed
+                        || self
+                            .stroke
+                            .dash_on(circle_outline_position(offset, mid_rad))
+                    {
+                        // share of the covered area that lies in the stroke band
+                        let stroke_weight = if outer_rad > inner_rad {
+                            (coverage - fill_coverage) / coverage
+                        } else {
+                            0.0
+                        };
+                        let color =
+                            color::lerp(fill.color_at(px, py), self.stroke.color, stroke_weight);
+                        self.plot(px, py, color, coverage);
+                    } else if fill_coverage > 0.0 {
+                        self.plot(px, py, fill.color_at(px, py), fill_coverage);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Offsets within `reach` of `center` on each axis that fall inside the
+    /// clip region.
+    fn clipped_offsets(
+        &self,
+        center: Vector2<isize>,
+        reach: isize,
+    ) -> (RangeInclusive<isize>, RangeInclusive<isize>) {
+        let clip = self.clip;
+        let x_range = (-reach).max(clip.x - center.x)..=reach.min(clip.right() - 1 - center.x);
+        let y_range = (-reach).max(clip.y - center.y)..=reach.min(clip.bottom() - 1 - center.y);
+        (x_range, y_range)
+    }
+
+    pub fn line(&mut self, start: Vector2<isize>, end: Vector2<isize>) {
+        let width = self.stroke.width as f32;
+        match self.placement() {
+            Placement::Offset(offset) => {
+                self.line_at((start + offset).cast(), (end + offset).cast(), width, 0.0)
+            }
+            Placement::General => {
+                // map pixel centers, then back to the integer pixel grid
+                let map = |p: Vector2<isize>| {
+                    self.transform_point(p.cast::<f32>().add_scalar(0.5))
+                        .add_scalar(-0.5)
+                };
+                let (start, end) = (map(start), map(end));
+                self.line_at(start, end, width * self.transform_scale(), 0.0);
+            }
+        }
+    }
+
+    /// Draws a screen-space line between pixel centers whose start lies
+    /// `dash_start` along the stroked path, in local units. Thick lines are
+    /// stroked as geometry with the current joins and caps.
+    pub(crate) fn line_at(
+        &mut self,
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        width: f32,
+        dash_start: f32,
+    ) {
+        if !self.is_hairline(width) {
+            let points = [start.add_scalar(0.5), end.add_scalar(0.5)];
+            self.stroke_path(&points, width, dash_start);
+            return;
+        }
+        // dash lengths are in local units, so undo the transform's scaling
+        let scale = match self.transform_scale() {
+            s if s > 0.0 => s,
+            _ => 1.0,
+        };
+        // only step through the visible part; pixel centers one pixel beyond
+        // the clip still feed the anti-aliased edge
+        let clip = self.clip;
+        let (start64, end64) = (start.cast::<f64>(), end.cast::<f64>());
+        let Some((t0, t1)) = raster::clip_segment(
+            start64,
+            end64,
+            Vector2::new(clip.x as f64 - 1.0, clip.y as f64 - 1.0),
+            Vector2::new(clip.right() as f64, clip.bottom() as f64),
+        ) else {
+            return;
+        };
+        let (start, end, dash_start) = if (t0, t1) == (0.0, 1.0) {
+            (start, end, dash_start)
+        } else {
+            let delta = end64 - start64;
+            (
+                (start64 + delta * t0).cast::<f32>(),
+                (start64 + delta * t1).cast::<f32>(),
+                self.stroke
+                    .dash_phase(dash_start as f64 + t0 * delta.norm() / scale as f64),
+            )
+        };
+        let length = (end - start).norm();
+        let direction = if length > 0.0 {
+            (end - start) / length
+        } else {
+            Vector2::zeros()
+        };
+        if self.antialias {
+            let color = self.stroke.color;
+            let runs = self
+                .stroke
+                .dash_runs(dash_start, dash_start + length / scale);
+            for (from, to) in runs {
+                let run_start = start + direction * ((from - dash_start) * scale);
+                let run_end = start + direction * ((to - dash_start) * scale);
+                raster::wu_line(run_start, run_end, |x, y, c| self.plot(x, y, color, c));
+            }
+            return;
+        }
+        // Bresenham's line algorithm
+        let (x0, y0) = (start.x.round() as isize, start.y.round() as isize);
+        let (x1, y1) = (end.x.round() as isize, end.y.round() as isize);
+
+        let dx = (x1 - x0).abs();
+        let dy = (y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx - dy;
+
+        let mut x = x0;
+        let mut y = y0;
+
+        loop {
+            let position = Vector2::new(x, y);
+            let along = (position.cast::<f32>() - start).dot(&direction) / scale;
+            if self.stroke.dash_on(dash_start + along) {
+                self.blend_pixel(position, self.stroke.color); // Draw a single pixel
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let err2 = err * 2;
+            if err2 > -dy {
+                err -= dy;
+                x += sx;
+            }
+            if err2 < dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn solid(&mut self, color: Pixel) {
+        if self.clip == Rect::new(0, 0, self.width, self.height) && self.threads <= 1 {
+            self.buffer.fill(color);
+            self.dirty.mark_all();
+            return;
+        }
+        let clip = self.clip;
+        self.for_each_row(clip, |_, row| row.fill(color));
+    }
+
+    pub fn clear(&mut self) {
+        self.solid(0);
+    }
+
+    pub fn is_in_bounds(&self, pos: Vector2<isize>) -> bool {
+        pos.x >= 0 && pos.y >= 0 && (pos.x as usize) < self.width && (pos.y as usize) < self.height
+    }
+
+    fn index(&self, pos: Vector2<isize>) -> usize {
+        pos.y as usize * self.width + pos.x as usize
+    }
+
+    /// Replaces the pixel at `pos`, or blends with it when a blend mode
+    /// other than `BlendMode::Normal` is set.
+    pub fn set_pixel(&mut self, pos: Vector2<isize>, pixel: Pixel) {
+        if self.clip.contains(pos) {
+            let index = self.index(pos);
+            self.dirty.mark(pos.x as usize, pos.y as usize);
+            self.buffer[index] = match self.blend_mode {
+                BlendMode::Normal => pixel,
+                mode => color::blend_with(self.buffer[index], pixel, mode),
+            };
+        }
+    }
+
+    /// Composites `pixel` over the buffer, honoring its alpha and the current
+    /// blend mode.
+    pub fn blend_pixel(&mut self, pos: Vector2<isize>, pixel: Pixel) {
+        if self.clip.contains(pos) {
+            let index = self.index(pos);
+            self.dirty.mark(pos.x as usize, pos.y as usize);
+            self.buffer[index] = color::blend_with(self.buffer[index], pixel, self.blend_mode);
+        }
+    }
+
+    /// Blends `color` at `(x, y)` scaled by a coverage in `0.0..=1.0`.
+    pub(crate) fn plot(&mut self, x: isize, y: isize, color: Pixel, coverage: f32) {
+        self.blend_pixel(Vector2::new(x, y), color::scale_alpha(color, coverage));
+    }
+
+    pub fn get_pixel(&self, pos: Vector2<isize>) -> Option<Pixel> {
+        if self.is_in_bounds(pos) {
+            Some(self.buffer[self.index(pos)])
+        } else {
+            None
+        }
+    }
+
+    pub fn text(&mut self, pos: Vector2<isize>, text: &str, scale: usize) {
+        let (x, y) = (pos.x, pos.y);
+        let font = get_font_5x7();
+        let char_width = 5;
+        let _char_height = 7;
+        let spacing = 1;
+
+        for (i, c) in text.chars().enumerate() {
+            if let Some(bitmap) = font.get(&c) {
+                let x_offset = x + (i * (char_width + spacing) * scale) as isize;
+
+                for (row, row_bits) in bitmap.iter().enumerate() {
+                    for col in 0..char_width {
+                        if (row_bits >> (char_width - 1 - col)) & 1 == 1 {
+                            let px = x_offset + (col * scale) as isize;
+                            let py = y + (row * scale) as isize;
+                            let stroke_width = self.stroke.width;
+                            let fill =
+                                std::mem::replace(&mut self.fill, Paint::Solid(self.stroke.color)); // Fill the stroke color
+                            self.stroke_width(0);
+                            self.rect(Vector2::new(px, py), scale, scale, false);
+                            self.fill = fill; // Restore the fill
+                            self.stroke_width(stroke_width);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn rect(&mut self, pos: Vector2<isize>, width: usize, height: usize, only_stroke: bool) {
+        match self.placement() {
+            Placement::Offset(offset) => self.rect_at(pos + offset, width, height, only_stroke),
+            Placement::General => self.rect_transformed(pos, width, height, only_stroke),
+        }
+    }
+
+    /// Outer bounds of a rect once its stroke is added.
+    fn rect_outer(&self, pos: Vector2<isize>, width: usize, height: usize) -> Rect {
+        let stroke = self.stroke.width;
+        match self.stroke.stroke_type {
+            StrokeType::Inner => Rect::new(pos.x, pos.y, width, height),
+            StrokeType::Outer => Rect::new(
+                pos.x - stroke as isize,
+                pos.y - stroke as isize,
+                width + stroke * 2,
+                height + stroke * 2,
+            ),
+            StrokeType::Center => Rect::new(
+                pos.x - (stroke / 2) as isize,
+                pos.y - (stroke / 2) as isize,
+                width + stroke,
+                height + stroke,
+            ),
+        }
+    }
+
+    pub(crate) fn rect_at(
+        &mut self,
+        pos: Vector2<isize>,
+        width: usize,
+        height: usize,
+        only_stroke: bool,
+    ) {
+        let stroke = self.stroke.width;
+        let outer = self.rect_outer(pos, width, height);
+        let fill = self.fill_sampler();
+        let (mid_min, mid_max) = stroke_midline(outer, stroke);
+        let (stroke_color, mode) = (self.stroke.color, self.blend_mode);
+        let dash = self.stroke.clone();
+        let start = outer.x.max(self.clip.x);
+        self.for_each_row(outer, |y, row| {
+            let dy = (y - outer.y) as usize;
+            for (i, pixel) in row.iter_mut().enumerate() {
+                let x = start + i as isize;
+                let dx = (x - outer.x) as usize;
+                let color = if dx < stroke
+                    || dx + stroke >= outer.width
+                    || dy < stroke
+                    || dy + stroke >= outer.height
+                {
+                    let center = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    if !dash.dash_on(rect_outline_position(mid_min, mid_max, center)) {
+                        continue;
+                    }
+                    stroke_color // Draw the stroke
+                } else if !only_stroke {
+                    fill.color_at(x, y) // Fill the inner rectangle
+                } else {
+                    continue;
+                };
+                *pixel = color::blend_with(*pixel, color, mode);
+            }
+        });
+    }
+
+    fn rect_transformed(
+        &mut self,
+        pos: Vector2<isize>,
+        width: usize,
+        height: usize,
+        only_stroke: bool,
+    ) {
+        let outer = self.rect_outer(pos, width, height);
+        let stroke = self.stroke.width;
+        let inner = Rect::new(
+            outer.x + stroke as isize,
+            outer.y + stroke as isize,
+            outer.width.saturating_sub(stroke * 2),
+            outer.height.saturating_sub(stroke * 2),
+        );
+        let contour = |rect: Rect| -> Vec<Vector2<f32>> {
+            let (x0, y0) = (rect.x as f32, rect.y as f32);
+            let (x1, y1) = (rect.right() as f32, rect.bottom() as f32);
+            [(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+                .iter()
+                .map(|&(x, y)| self.transform_point(Vector2::new(x, y)))
+                .collect()
+        };
+        let (outer_contour, inner_contour) = (contour(outer), contour(inner));
+
+        let (mid_min, mid_max) = stroke_midline(outer, stroke);
+        let stroke_style = self.stroke.clone();
+        let on_dash = |local| stroke_style.dash_on(rect_outline_position(mid_min, mid_max, local));
+        let stroke_paint = Paint::Solid(self.stroke.color);
+
+        if inner.is_empty() {
+            self.fill_contours_masked(&[outer_contour], &stroke_paint, on_dash);
+            return;
+        }
+        if stroke > 0 {
+            self.fill_contours_masked(
+                &[outer_contour, inner_contour.clone()],
+                &stroke_paint,
+                on_dash,
+            );
+        }
+        if !only_stroke {
+            self.fill_contours(&[inner_contour], &self.fill.clone());
+        }
+    }
+
+    /// Images are placed at the transformed position of `pos` but are not
+    /// rotated or scaled. The current stroke is drawn around the image; use
+    /// `blit` for more control.
+    pub fn draw_buffer(
+        &mut self,
+        pos: Vector2<isize>,
+        buffer: &[Pixel],
+        width: usize,
+        height: usize,
+    ) {
+        let options = BlitOptions {
+            outline: true,
+            ..BlitOptions::default()
+        };
+        let src = Rect::new(0, 0, width, height);
+        self.blit_pixels(buffer, width, height, src, pos, &options);
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        image::save_png(path, &self.buffer, self.width, self.height)
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
+
+/// Corners of the line running through the middle of a rect's stroke band,
+/// in pixel-edge coordinates.
+fn stroke_midline(outer: Rect, stroke: usize) -> (Vector2<f32>, Vector2<f32>) {
+    let half = stroke as f32 / 2.0;
+    (
+        Vector2::new(outer.x as f32 + half, outer.y as f32 + half),
+        Vector2::new(outer.right() as f32 - half, outer.bottom() as f32 - half),
+    )
+}
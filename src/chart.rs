@@ -1,61 +1,47 @@
-use crate::{Pixel, Stroke};
+use std::ops::{Deref, DerefMut};
+
+use nalgebra::Vector2;
+
+use crate::{Paint, Pixel, Stroke, StrokeType, Surface};
 
 pub enum ChartType {
     Dots,
     Lines,
+    /// Lines with the area between the series and the x axis filled with the
+    /// chart's fill paint.
+    Area,
 }
 
+/// Plots data into its own `Surface`. Stroke, fill and anti-aliasing are set
+/// through the surface (`Chart` derefs to it), and the finished chart can be
+/// composited onto the screen like any other layer.
 pub struct Chart {
     pub width: usize,
     pub height: usize,
     x_range: (f32, f32),
     y_range: (f32, f32),
-    fill: Pixel,
-    stroke: Stroke,
     chart_axis: Stroke,
-    pub buffer: Vec<Pixel>,
+    surface: Surface,
 }
 
 impl Chart {
     pub fn new(width: usize, height: usize, x_range: (f32, f32), y_range: (f32, f32)) -> Self {
-        let fill = 0x000000; // Default fill color (black)
-        let stroke = Stroke {
-            color: 0xFFFFFF,                       // Default stroke color (white)
-            width: 1,                              // Default stroke width
-            stroke_type: crate::StrokeType::Outer, // Default stroke type (solid)
-        };
+        let mut surface = Surface::new(width, height);
+        surface.stroke_type(StrokeType::Outer);
 
         Chart {
             width,
             height,
             x_range,
             y_range,
-            fill,
-            stroke,
-            chart_axis: Stroke {
-                color: 0x444444, // Default axis color (white)
-                width: 1,        // Default axis width
-                stroke_type: crate::StrokeType::Outer,
-            },
-            buffer: vec![0x000000; width * height], // Initialize with black color
+            chart_axis: Stroke::new(0x444444, 1, StrokeType::Outer), // Default axis color (grey)
+            surface,
         }
     }
 
-    pub fn fill(&mut self, color: u32) {
-        self.fill = color;
-    }
-
     pub fn stroke(&mut self, color: u32, width: usize) {
-        self.stroke.color = color;
-        self.stroke.width = width;
-    }
-
-    pub fn stroke_color(&mut self, color: Pixel) {
-        self.stroke.color = color;
-    }
-
-    pub fn stroke_width(&mut self, width: usize) {
-        self.stroke.width = width;
+        self.surface.stroke_color(color);
+        self.surface.stroke_width(width);
     }
 
     pub fn chart_color(&mut self, color: Pixel) {
@@ -65,62 +51,24 @@ impl Chart {
         self.chart_axis.width = width;
     }
 
-    fn is_in_bounds(&mut self, x: usize, y: usize) -> bool {
-        x < self.width && y < self.height
+    /// Filled dot in the stroke color.
+    fn dot(&mut self, (x, y): (usize, usize), radius: usize) {
+        let surface = &mut self.surface;
+        let fill = std::mem::replace(&mut surface.fill, Paint::Solid(surface.stroke.color));
+        let width = surface.stroke.width;
+        surface.stroke_width(0);
+        surface.circle(Vector2::new(x as isize, y as isize), radius);
+        surface.stroke_width(width);
+        surface.fill = fill;
     }
 
-    fn set_pixel(&mut self, x: usize, y: usize, color: Pixel) {
-        if self.is_in_bounds(x, y) {
-            self.buffer[y * self.width + x] = color;
-        }
-    }
-
-    fn circle(&mut self, cx: usize, cy: usize, radius: usize) {
-        for y in -(radius as isize)..=radius as isize {
-            for x in -(radius as isize)..=(radius as isize) {
-                let distance_sq = x * x + y * y;
-                if distance_sq <= (radius as isize) * (radius as isize) {
-                    let px = cx as isize + x;
-                    let py = cy as isize + y;
-                    if self.is_in_bounds(px as usize, py as usize) {
-                        self.set_pixel(px as usize, py as usize, self.stroke.color);
-                    }
-                }
-            }
-        }
-    }
-    fn line(&mut self, start: (usize, usize), end: (usize, usize)) {
-        let (x0, y0) = start;
-        let (x1, y1) = end;
-
-        let dx = (x1 as isize - x0 as isize).abs();
-        let dy = (y1 as isize - y0 as isize).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
-
-        let mut x = x0;
-        let mut y = y0;
-
-        loop {
-            if self.stroke.width == 1 {
-                self.set_pixel(x, y, self.stroke.color); // Draw a single pixel
-            } else {
-                self.circle(x, y, self.stroke.width / 2); // Draw a circle for thickness
-            }
-            if x == x1 && y == y1 {
-                break;
-            }
-            let err2 = err * 2;
-            if err2 > -dy {
-                err -= dy;
-                x = (x as isize + sx) as usize;
-            }
-            if err2 < dx {
-                err += dx;
-                y = (y as isize + sy) as usize;
-            }
-        }
+    /// Draws a line between pixel centers, starting `dash_start` pixels into
+    /// the stroke's dash pattern.
+    fn line(&mut self, start: (usize, usize), end: (usize, usize), dash_start: f32) {
+        let start = Vector2::new(start.0 as f32, start.1 as f32);
+        let end = Vector2::new(end.0 as f32, end.1 as f32);
+        let width = self.surface.stroke.width as f32;
+        self.surface.line_at(start, end, width, dash_start);
     }
 
     fn to_buffer_coordinates(&self, x: f32, y: f32) -> (usize, usize) {
@@ -149,10 +97,9 @@ impl Chart {
             None
         };
         if let Some(x) = zero_x {
-            let stroke = self.stroke;
-            self.stroke = self.chart_axis;
-            self.line((x, 0), (x, self.height - 1)); // asse x
-            self.stroke = stroke;
+            let stroke = std::mem::replace(&mut self.surface.stroke, self.chart_axis.clone());
+            self.line((x, 0), (x, self.height - 1), 0.0); // asse x
+            self.surface.stroke = stroke;
         }
 
         // Draw y-axis
@@ -162,10 +109,9 @@ impl Chart {
             None
         };
         if let Some(y) = zero_y {
-            let stroke = self.stroke;
-            self.stroke = self.chart_axis;
-            self.line((0, y), (self.width - 1, y)); // asse x
-            self.stroke = stroke;
+            let stroke = std::mem::replace(&mut self.surface.stroke, self.chart_axis.clone());
+            self.line((0, y), (self.width - 1, y), 0.0); // asse x
+            self.surface.stroke = stroke;
         }
     }
 
@@ -175,21 +121,65 @@ impl Chart {
         match chart_type {
             ChartType::Dots => {
                 for &(x, y) in data {
-                    let (bx, by) = self.to_buffer_coordinates(x, y);
-                    self.circle(bx, by, self.stroke.width / 2);
+                    let point = self.to_buffer_coordinates(x, y);
+                    self.dot(point, self.surface.stroke.width / 2);
                 }
             }
-            ChartType::Lines => {
-                for i in 0..data.len() - 1 {
-                    let (x1, y1) = data[i];
-                    let (x2, y2) = data[i + 1];
+            ChartType::Lines => self.series_lines(data),
+            ChartType::Area => {
+                self.area(data);
+                self.series_lines(data);
+            }
+        }
+    }
 
-                    let (bx1, by1) = self.to_buffer_coordinates(x1, y1);
-                    let (bx2, by2) = self.to_buffer_coordinates(x2, y2);
+    fn series_lines(&mut self, data: &[(f32, f32)]) {
+        let mut dash_start = 0.0;
+        for pair in data.windows(2) {
+            let (x1, y1) = pair[0];
+            let (x2, y2) = pair[1];
 
-                    self.line((bx1, by1), (bx2, by2));
-                }
-            }
+            let (bx1, by1) = self.to_buffer_coordinates(x1, y1);
+            let (bx2, by2) = self.to_buffer_coordinates(x2, y2);
+
+            self.line((bx1, by1), (bx2, by2), dash_start);
+            dash_start += (bx2 as f32 - bx1 as f32).hypot(by2 as f32 - by1 as f32);
         }
     }
+
+    /// Fills between the series and `y = 0`, or the nearest edge of the
+    /// chart when zero is out of range.
+    fn area(&mut self, data: &[(f32, f32)]) {
+        let (Some(&(first_x, _)), Some(&(last_x, _))) = (data.first(), data.last()) else {
+            return;
+        };
+        let (y_min, y_max) = self.y_range;
+        let baseline = 0.0f32.clamp(y_min.min(y_max), y_min.max(y_max));
+
+        // buffer coordinates are pixel centers, polygons use pixel edges
+        let to_point = |(x, y): (usize, usize)| Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+        let mut contour = vec![to_point(self.to_buffer_coordinates(first_x, baseline))];
+        contour.extend(
+            data.iter()
+                .map(|&(x, y)| to_point(self.to_buffer_coordinates(x, y))),
+        );
+        contour.push(to_point(self.to_buffer_coordinates(last_x, baseline)));
+
+        let fill = self.surface.fill.clone();
+        self.surface.fill_contours(&[contour], &fill);
+    }
+}
+
+impl Deref for Chart {
+    type Target = Surface;
+
+    fn deref(&self) -> &Surface {
+        &self.surface
+    }
+}
+
+impl DerefMut for Chart {
+    fn deref_mut(&mut self) -> &mut Surface {
+        &mut self.surface
+    }
 }
@@ -0,0 +1,106 @@
+use crate::{BlendMode, Pixel};
+
+pub fn rgb(r: u8, g: u8, b: u8) -> Pixel {
+    ((r as Pixel) << 16) | ((g as Pixel) << 8) | b as Pixel
+}
+
+/// Builds a pixel from straight (non-inverted) alpha, `255` being opaque.
+pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Pixel {
+    ((255 - a as Pixel) << 24) | rgb(r, g, b)
+}
+
+pub fn with_alpha(pixel: Pixel, a: u8) -> Pixel {
+    ((255 - a as Pixel) << 24) | (pixel & 0xFFFFFF)
+}
+
+pub fn alpha(pixel: Pixel) -> u8 {
+    255 - (pixel >> 24) as u8
+}
+
+pub fn channels(pixel: Pixel) -> (u8, u8, u8) {
+    ((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8)
+}
+
+pub fn is_opaque(pixel: Pixel) -> bool {
+    pixel >> 24 == 0
+}
+
+/// Composites `src` over `dst` (Porter-Duff source-over).
+pub fn blend(dst: Pixel, src: Pixel) -> Pixel {
+    let sa = alpha(src) as u32;
+    if sa == 255 {
+        return src;
+    }
+    if sa == 0 {
+        return dst;
+    }
+    let da = alpha(dst) as u32 * (255 - sa) / 255;
+    let out_a = sa + da;
+
+    let (sr, sg, sb) = channels(src);
+    let (dr, dg, db) = channels(dst);
+    let mix = |s: u8, d: u8| ((s as u32 * sa + d as u32 * da + out_a / 2) / out_a) as u8;
+    rgba(mix(sr, dr), mix(sg, dg), mix(sb, db), out_a as u8)
+}
+
+/// Multiplies the alpha of `pixel` by `coverage` (`0.0..=1.0`).
+pub fn scale_alpha(pixel: Pixel, coverage: f32) -> Pixel {
+    if coverage >= 1.0 {
+        return pixel;
+    }
+    with_alpha(pixel, (alpha(pixel) as f32 * coverage).round() as u8)
+}
+
+/// Linear interpolation between two pixels, alpha included.
+pub fn lerp(from: Pixel, to: Pixel, t: f32) -> Pixel {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |shift: u32| {
+        let a = ((from >> shift) & 0xFF) as f32;
+        let b = ((to >> shift) & 0xFF) as f32;
+        ((a + (b - a) * t).round() as u32) << shift
+    };
+    mix(24) | mix(16) | mix(8) | mix(0)
+}
+
+/// Channel-wise product of two pixels, alpha included; multiplying by white
+/// leaves a pixel unchanged.
+pub fn multiply(pixel: Pixel, tint: Pixel) -> Pixel {
+    let (pr, pg, pb) = channels(pixel);
+    let (tr, tg, tb) = channels(tint);
+    let mul = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+    rgba(
+        mul(pr, tr),
+        mul(pg, tg),
+        mul(pb, tb),
+        mul(alpha(pixel), alpha(tint)),
+    )
+}
+
+/// Composites `src` over `dst` using `mode` for the color channels: the
+/// blended color is used where `dst` is opaque and `src` as is where it is
+/// transparent, then the result goes over `dst` with `src`'s alpha.
+pub fn blend_with(dst: Pixel, src: Pixel, mode: BlendMode) -> Pixel {
+    let function: fn(f32, f32) -> f32 = match mode {
+        BlendMode::Normal => return blend(dst, src),
+        BlendMode::Multiply => |d, s| d * s,
+        BlendMode::Screen => |d, s| d + s - d * s,
+        BlendMode::Add => |d, s| (d + s).min(1.0),
+        BlendMode::Overlay => |d, s| {
+            if d <= 0.5 {
+                2.0 * d * s
+            } else {
+                1.0 - 2.0 * (1.0 - d) * (1.0 - s)
+            }
+        },
+        BlendMode::Difference => |d, s| (d - s).abs(),
+    };
+    let dst_alpha = alpha(dst) as f32 / 255.0;
+    let (sr, sg, sb) = channels(src);
+    let (dr, dg, db) = channels(dst);
+    let mix = |d: u8, s: u8| {
+        let (d, s) = (d as f32 / 255.0, s as f32 / 255.0);
+        let blended = s + (function(d, s) - s) * dst_alpha;
+        (blended * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    blend(dst, rgba(mix(dr, sr), mix(dg, sg), mix(db, sb), alpha(src)))
+}
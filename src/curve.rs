@@ -0,0 +1,119 @@
+//! Bézier curves. Curves are flattened into polylines by recursive
+//! subdivision, splitting more where the curve bends more.
+
+use nalgebra::Vector2;
+
+use crate::Canvas;
+
+/// Default maximum distance, in pixels, between a curve and its flattening.
+pub const FLATNESS: f32 = 0.25;
+
+const MAX_DEPTH: usize = 16;
+
+/// Points along a quadratic Bézier, `p0` and `p2` included.
+pub fn flatten_quad(
+    p0: Vector2<f32>,
+    p1: Vector2<f32>,
+    p2: Vector2<f32>,
+    tolerance: f32,
+) -> Vec<Vector2<f32>> {
+    let mut points = vec![p0];
+    subdivide_quad(p0, p1, p2, tolerance, 0, &mut points);
+    points
+}
+
+/// Points along a cubic Bézier, `p0` and `p3` included.
+pub fn flatten_cubic(
+    p0: Vector2<f32>,
+    p1: Vector2<f32>,
+    p2: Vector2<f32>,
+    p3: Vector2<f32>,
+    tolerance: f32,
+) -> Vec<Vector2<f32>> {
+    let mut points = vec![p0];
+    subdivide_cubic(p0, p1, p2, p3, tolerance, 0, &mut points);
+    points
+}
+
+fn subdivide_quad(
+    p0: Vector2<f32>,
+    p1: Vector2<f32>,
+    p2: Vector2<f32>,
+    tolerance: f32,
+    depth: usize,
+    out: &mut Vec<Vector2<f32>>,
+) {
+    // the curve never strays further than a quarter of the second difference
+    // from its chord
+    let deviation = (p0 - p1 * 2.0 + p2).norm() / 4.0;
+    if depth >= MAX_DEPTH || deviation <= tolerance {
+        out.push(p2);
+        return;
+    }
+    let p01 = (p0 + p1) / 2.0;
+    let p12 = (p1 + p2) / 2.0;
+    let mid = (p01 + p12) / 2.0;
+    subdivide_quad(p0, p01, mid, tolerance, depth + 1, out);
+    subdivide_quad(mid, p12, p2, tolerance, depth + 1, out);
+}
+
+fn subdivide_cubic(
+    p0: Vector2<f32>,
+    p1: Vector2<f32>,
+    p2: Vector2<f32>,
+    p3: Vector2<f32>,
+    tolerance: f32,
+    depth: usize,
+    out: &mut Vec<Vector2<f32>>,
+) {
+    let deviation = (p0 - p1 * 2.0 + p2).norm().max((p1 - p2 * 2.0 + p3).norm()) * 0.75;
+    if depth >= MAX_DEPTH || deviation <= tolerance {
+        out.push(p3);
+        return;
+    }
+    let p01 = (p0 + p1) / 2.0;
+    let p12 = (p1 + p2) / 2.0;
+    let p23 = (p2 + p3) / 2.0;
+    let p012 = (p01 + p12) / 2.0;
+    let p123 = (p12 + p23) / 2.0;
+    let mid = (p012 + p123) / 2.0;
+    subdivide_cubic(p0, p01, p012, mid, tolerance, depth + 1, out);
+    subdivide_cubic(mid, p123, p23, p3, tolerance, depth + 1, out);
+}
+
+impl Canvas {
+    /// Quadratic Bézier from `p0` to `p2` bent towards `p1`, drawn with the
+    /// current stroke. A curve ending where it starts is also filled.
+    pub fn quad_bezier(&mut self, p0: Vector2<f32>, p1: Vector2<f32>, p2: Vector2<f32>) {
+        let [p0, p1, p2] = [p0, p1, p2].map(|p| self.transform_point(p));
+        let points = flatten_quad(p0, p1, p2, FLATNESS);
+        self.draw_curve(points);
+    }
+
+    /// Cubic Bézier from `p0` to `p3` with control points `p1` and `p2`, drawn
+    /// with the current stroke. A curve ending where it starts is also filled.
+    pub fn cubic_bezier(
+        &mut self,
+        p0: Vector2<f32>,
+        p1: Vector2<f32>,
+        p2: Vector2<f32>,
+        p3: Vector2<f32>,
+    ) {
+        let [p0, p1, p2, p3] = [p0, p1, p2, p3].map(|p| self.transform_point(p));
+        let points = flatten_cubic(p0, p1, p2, p3, FLATNESS);
+        self.draw_curve(points);
+    }
+
+    /// Draws flattened screen-space points, given in pixel-edge coordinates
+    /// like `polygon`.
+    fn draw_curve(&mut self, mut points: Vec<Vector2<f32>>) {
+        let closed = points.len() > 2 && (points[0] - points[points.len() - 1]).norm() < 1e-3;
+        if closed {
+            points.pop();
+            self.paint_outline(&[points], false);
+            return;
+        }
+        let width = self.stroke.width as f32 * self.transform_scale();
+        self.stroke_path(&points, width, 0.0);
+    }
+}
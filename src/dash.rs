@@ -0,0 +1,140 @@
+//! Dash patterns. Positions are distances along the stroked path; a pattern
+//! with an odd number of entries is repeated once so on and off alternate.
+
+use std::f32::consts::TAU;
+
+use nalgebra::Vector2;
+
+use crate::Stroke;
+
+/// Patterns repeating in less than this many pixels draw solid; finer ones
+/// cannot be told apart and would produce countless runs.
+const MIN_DASH_PERIOD: f32 = 0.5;
+
+/// Spans covering more periods than this draw solid rather than building
+/// millions of runs.
+const MAX_DASH_PERIODS: f32 = 1_000_000.0;
+
+impl Stroke {
+    pub fn is_dashed(&self) -> bool {
+        self.dash_period() > 0.0
+    }
+
+    fn dash_entries(&self) -> impl Iterator<Item = f32> + '_ {
+        let repeat = if self.dash.len() % 2 == 1 { 2 } else { 1 };
+        self.dash
+            .iter()
+            .cycle()
+            .take(self.dash.len() * repeat)
+            .map(|&len| len.max(0.0))
+    }
+
+    fn dash_period(&self) -> f32 {
+        let period: f32 = self.dash_entries().sum();
+        if period.is_finite() && period >= MIN_DASH_PERIOD {
+            period
+        } else {
+            0.0
+        }
+    }
+
+    /// `dash_offset` wrapped into one `period`; offsets that are not finite
+    /// count as 0.
+    fn dash_shift(&self, period: f32) -> f32 {
+        if self.dash_offset.is_finite() {
+            self.dash_offset.rem_euclid(period)
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether the pattern draws at `distance` along the path.
+    pub(crate) fn dash_on(&self, distance: f32) -> bool {
+        let period = self.dash_period();
+        if period <= 0.0 {
+            return true;
+        }
+        let mut t = (distance + self.dash_shift(period)).rem_euclid(period);
+        for (i, len) in self.dash_entries().enumerate() {
+            if t < len {
+                return i % 2 == 0;
+            }
+            t -= len;
+        }
+        false
+    }
+
+    /// `distance` wrapped into a single period of the pattern, which keeps
+    /// distances far along a path precise in `f32`; `0.0` for solid strokes.
+    pub(crate) fn dash_phase(&self, distance: f64) -> f32 {
+        let period = self.dash_period();
+        if period <= 0.0 {
+            return 0.0;
+        }
+        distance.rem_euclid(period as f64) as f32
+    }
+
+    /// Drawn parts of the path between the distances `from` and `to`.
+    pub(crate) fn dash_runs(&self, from: f32, to: f32) -> Vec<(f32, f32)> {
+        let period = self.dash_period();
+        if period <= 0.0 || !(to - from).is_finite() || (to - from) / period > MAX_DASH_PERIODS {
+            return vec![(from, to)];
+        }
+        // where each entry starts within a period
+        let mut starts = Vec::new();
+        let mut start = 0.0;
+        for len in self.dash_entries() {
+            starts.push((start, len));
+            start += len;
+        }
+
+        // boundaries are counted in whole periods from the start of the one
+        // `from` falls into, rather than summed up, so the loop always
+        // advances and ends
+        let period = period as f64;
+        let phase = (from as f64 + self.dash_shift(period as f32) as f64).rem_euclid(period);
+        let span = (to - from) as f64;
+        let mut runs = Vec::new();
+        let mut index = 0.0;
+        loop {
+            let base = index * period - phase;
+            if base >= span {
+                break;
+            }
+            for &(start, len) in starts.iter().step_by(2) {
+                let on = (from as f64 + base + start as f64) as f32;
+                let off = (from as f64 + base + (start + len) as f64) as f32;
+                let (a, b) = (on.max(from), off.min(to));
+                if a < b {
+                    runs.push((a, b));
+                }
+            }
+            index += 1.0;
+        }
+        runs
+    }
+}
+
+/// Distance, clockwise from the top-left corner, of the point on the outline
+/// of the rectangle `min`-`max` nearest to `p`.
+pub(crate) fn rect_outline_position(min: Vector2<f32>, max: Vector2<f32>, p: Vector2<f32>) -> f32 {
+    let size = max - min;
+    let q = p - min;
+    let (x, y) = (q.x.clamp(0.0, size.x), q.y.clamp(0.0, size.y));
+    let sides = [
+        (q.y.abs(), x),
+        ((q.x - size.x).abs(), size.x + y),
+        ((q.y - size.y).abs(), size.x + size.y + (size.x - x)),
+        (q.x.abs(), size.x * 2.0 + size.y + (size.y - y)),
+    ];
+    sides
+        .iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map_or(0.0, |side| side.1)
+}
+
+/// Distance along a circle of `radius`, clockwise from the positive x axis,
+/// to the direction of `offset`.
+pub(crate) fn circle_outline_position(offset: Vector2<f32>, radius: f32) -> f32 {
+    offset.y.atan2(offset.x).rem_euclid(TAU) * radius
+}
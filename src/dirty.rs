@@ -0,0 +1,113 @@
+//! Tracking of the areas written since the last present, at the granularity
+//! of square tiles so that marking a pixel stays a single store.
+
+use nalgebra::Vector2;
+
+use crate::{Canvas, Rect};
+
+/// Side of a dirty-tracking tile in pixels.
+const TILE: usize = 16;
+
+#[derive(Debug, Clone)]
+pub(crate) struct DirtyTiles {
+    columns: usize,
+    rows: usize,
+    tiles: Vec<bool>,
+}
+
+impl DirtyTiles {
+    /// A new canvas has never been presented, so it starts out all dirty.
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        let (columns, rows) = (width.div_ceil(TILE), height.div_ceil(TILE));
+        DirtyTiles {
+            columns,
+            rows,
+            tiles: vec![true; columns * rows],
+        }
+    }
+
+    /// Marks the pixel at `(x, y)`, which must be inside the buffer.
+    pub(crate) fn mark(&mut self, x: usize, y: usize) {
+        self.tiles[y / TILE * self.columns + x / TILE] = true;
+    }
+
+    /// Marks `area`, which must be non-empty and inside the buffer.
+    pub(crate) fn mark_rect(&mut self, area: Rect) {
+        let (left, right) = (area.x as usize / TILE, (area.right() as usize - 1) / TILE);
+        for row in area.y as usize / TILE..=(area.bottom() as usize - 1) / TILE {
+            self.tiles[row * self.columns + left..=row * self.columns + right].fill(true);
+        }
+    }
+
+    pub(crate) fn mark_all(&mut self) {
+        self.tiles.fill(true);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.tiles.fill(false);
+    }
+
+    /// Dirty tiles merged into non-overlapping rects: runs of tiles along a
+    /// row, extended downwards while the rows below have the same run.
+    fn regions(&self, width: usize, height: usize) -> Vec<Rect> {
+        let mut done: Vec<Rect> = Vec::new();
+        // runs of the previous tile row, as (first column, end column, rect)
+        let mut open: Vec<(usize, usize, Rect)> = Vec::new();
+        for row in 0..self.rows {
+            let tiles = &self.tiles[row * self.columns..(row + 1) * self.columns];
+            let mut runs = Vec::new();
+            let mut column = 0;
+            while column < self.columns {
+                if !tiles[column] {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < self.columns && tiles[column] {
+                    column += 1;
+                }
+                runs.push((start, column));
+            }
+
+            let mut next = Vec::with_capacity(runs.len());
+            for (start, end) in runs {
+                let band = Rect::from_corners(
+                    Vector2::new((start * TILE) as isize, (row * TILE) as isize),
+                    Vector2::new(
+                        (end * TILE).min(width) as isize,
+                        ((row + 1) * TILE).min(height) as isize,
+                    ),
+                );
+                let rect = match open.iter().position(|&(s, e, _)| (s, e) == (start, end)) {
+                    Some(i) => open.swap_remove(i).2.union(&band),
+                    None => band,
+                };
+                next.push((start, end, rect));
+            }
+            done.extend(open.into_iter().map(|(_, _, rect)| rect));
+            open = next;
+        }
+        done.extend(open.into_iter().map(|(_, _, rect)| rect));
+        done.sort_by_key(|rect| (rect.y, rect.x));
+        done
+    }
+}
+
+impl Canvas {
+    /// Areas written since the canvas was created or last presented,
+    /// rounded out to 16-pixel tiles. They do not overlap.
+    pub fn dirty_regions(&self) -> Vec<Rect> {
+        self.dirty.regions(self.get_width(), self.get_height())
+    }
+
+    /// Forgets the written areas; `Screen::draw` does this after presenting.
+    pub fn reset_dirty(&mut self) {
+        self.dirty.reset();
+    }
+
+    /// Marks the whole canvas as changed, e.g. after writing to `buffer`
+    /// directly, which is not tracked.
+    pub fn mark_dirty(&mut self) {
+        self.dirty.mark_all();
+    }
+}
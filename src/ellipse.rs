@@ -0,0 +1,74 @@
+use std::f32::consts::TAU;
+
+use nalgebra::Vector2;
+
+use crate::{Canvas, Paint};
+
+impl Canvas {
+    /// Ellipse centered on the `center` pixel, with the same stroke handling as
+    /// `circle`.
+    pub fn ellipse(&mut self, center: Vector2<isize>, rx: usize, ry: usize) {
+        let center = center.cast::<f32>().add_scalar(0.5);
+        let radii = Vector2::new(rx as f32, ry as f32).add_scalar(0.5);
+        let contour = self.arc_points(center, radii, 0.0, TAU);
+        self.paint_outline(&[contour], false);
+    }
+
+    /// Open arc of radius `radius` from `start` to `end`, in radians clockwise
+    /// from the positive x axis. Only the stroke is drawn, placed on the
+    /// radius according to the `StrokeType`.
+    pub fn arc(&mut self, center: Vector2<isize>, radius: usize, start: f32, end: f32) {
+        let (inner_rad, outer_rad) = self.ring_radii(radius);
+        if outer_rad == inner_rad {
+            return;
+        }
+        let center = center.cast::<f32>().add_scalar(0.5);
+        let sweep = (end - start).clamp(-TAU, TAU);
+        let outer = self.arc_points(
+            center,
+            Vector2::repeat(outer_rad as f32 + 0.5),
+            start,
+            sweep,
+        );
+        let inner = self.arc_points(
+            center,
+            Vector2::repeat(inner_rad as f32 + 0.5),
+            start,
+            sweep,
+        );
+        let band = outer.into_iter().chain(inner.into_iter().rev()).collect();
+        self.fill_contours(&[band], &Paint::Solid(self.stroke.color));
+    }
+
+    /// Pie wedge from `start` to `end`, in radians clockwise from the positive
+    /// x axis, filled and outlined like `circle`.
+    pub fn pie(&mut self, center: Vector2<isize>, radius: usize, start: f32, end: f32) {
+        let center = center.cast::<f32>().add_scalar(0.5);
+        let sweep = (end - start).clamp(-TAU, TAU);
+        let mut contour = vec![self.transform_point(center)];
+        contour.extend(self.arc_points(center, Vector2::repeat(radius as f32 + 0.5), start, sweep));
+        self.paint_outline(&[contour], false);
+    }
+
+    /// Screen-space points along an elliptical arc given in local space.
+    pub(crate) fn arc_points(
+        &self,
+        center: Vector2<f32>,
+        radii: Vector2<f32>,
+        start: f32,
+        sweep: f32,
+    ) -> Vec<Vector2<f32>> {
+        let screen_radius = radii.max() * self.transform_scale();
+        let full_circle = (7.0 * screen_radius.sqrt()).ceil().max(16.0);
+        let segments = (full_circle * sweep.abs() / TAU).ceil().max(2.0) as usize;
+        let closed = sweep.abs() >= TAU;
+        let count = if closed { segments } else { segments + 1 };
+        (0..count)
+            .map(|i| {
+                let angle = start + sweep * i as f32 / segments as f32;
+                let local = center + Vector2::new(angle.cos() * radii.x, angle.sin() * radii.y);
+                self.transform_point(local)
+            })
+            .collect()
+    }
+}
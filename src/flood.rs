@@ -0,0 +1,86 @@
+use nalgebra::Vector2;
+
+use crate::transform::Placement;
+use crate::{Canvas, Connectivity, Pixel, color};
+
+impl Canvas {
+    /// Bucket fill: paints the 4-connected region of exactly the color found
+    /// at `pos`.
+    pub fn flood_fill(&mut self, pos: Vector2<isize>, color: Pixel) {
+        self.flood_fill_with(pos, color, Connectivity::Four, 0);
+    }
+
+    /// Paints the region around `pos` whose pixels differ from the one at
+    /// `pos` by at most `tolerance` in every channel, alpha included. The
+    /// region grows through `connectivity` neighbours and stops at the clip.
+    pub fn flood_fill_with(
+        &mut self,
+        pos: Vector2<isize>,
+        color: Pixel,
+        connectivity: Connectivity,
+        tolerance: u8,
+    ) {
+        let pos = match self.placement() {
+            Placement::Offset(offset) => pos + offset,
+            Placement::General => {
+                let mapped = self.transform_point(pos.cast::<f32>().add_scalar(0.5));
+                Vector2::new(mapped.x.floor() as isize, mapped.y.floor() as isize)
+            }
+        };
+        let clip = self.clip;
+        if !clip.contains(pos) {
+            return;
+        }
+        let Some(target) = self.get_pixel(pos) else {
+            return;
+        };
+        let matches = |pixel: Pixel| within_tolerance(pixel, target, tolerance);
+
+        let neighbours: &[(isize, isize)] = match connectivity {
+            Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+            Connectivity::Eight => &[
+                (1, 0),
+                (-1, 0),
+                (0, 1),
+                (0, -1),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+                (-1, -1),
+            ],
+        };
+        // pixels already queued; the buffer changes under us when `color`
+        // itself is within tolerance, so it cannot tell us
+        let mut seen = vec![false; clip.width * clip.height];
+        let seen_index =
+            |p: Vector2<isize>| (p.y - clip.y) as usize * clip.width + (p.x - clip.x) as usize;
+        seen[seen_index(pos)] = true;
+        let mut stack = vec![pos];
+        while let Some(point) = stack.pop() {
+            self.blend_pixel(point, color);
+            for &(dx, dy) in neighbours {
+                let next = Vector2::new(point.x + dx, point.y + dy);
+                if !clip.contains(next) || seen[seen_index(next)] {
+                    continue;
+                }
+                if self.get_pixel(next).is_some_and(matches) {
+                    seen[seen_index(next)] = true;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+}
+
+fn within_tolerance(a: Pixel, b: Pixel, tolerance: u8) -> bool {
+    let (ar, ag, ab) = color::channels(a);
+    let (br, bg, bb) = color::channels(b);
+    [
+        (ar, br),
+        (ag, bg),
+        (ab, bb),
+        (color::alpha(a), color::alpha(b)),
+    ]
+    .iter()
+    .all(|&(x, y)| x.abs_diff(y) <= tolerance)
+}
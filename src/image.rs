@@ -0,0 +1,443 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Pixel;
+use crate::color::{self, rgb, rgba};
+
+/// Decoded image in the crate's `Pixel` format, alpha included, ready for
+/// `Canvas::draw_buffer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Pixel>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    UnknownFormat,
+    Unsupported(String),
+    Malformed(String),
+    Png(png::DecodingError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "io error: {}", e),
+            DecodeError::UnknownFormat => write!(f, "unknown image format"),
+            DecodeError::Unsupported(what) => write!(f, "unsupported image: {}", what),
+            DecodeError::Malformed(what) => write!(f, "malformed image: {}", what),
+            DecodeError::Png(e) => write!(f, "png error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for DecodeError {
+    fn from(e: png::DecodingError) -> Self {
+        DecodeError::Png(e)
+    }
+}
+
+fn malformed(what: &str) -> DecodeError {
+    DecodeError::Malformed(what.to_string())
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![0x000000; width * height],
+        }
+    }
+
+    /// Loads a PPM, BMP or PNG file, picking the decoder from the file contents.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DecodeError> {
+        Image::decode(&fs::read(path)?)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.starts_with(b"\x89PNG") {
+            decode_png(bytes)
+        } else if bytes.starts_with(b"BM") {
+            decode_bmp(bytes)
+        } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+            decode_ppm(bytes)
+        } else {
+            Err(DecodeError::UnknownFormat)
+        }
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        save_png(path, &self.pixels, self.width, self.height)
+    }
+}
+
+/// Encodes a buffer as an 8-bit PNG. Fully opaque buffers are written as RGB,
+/// anything with transparency as RGBA. Fails with `InvalidInput` when the
+/// buffer does not hold `width * height` pixels.
+pub fn encode_png(buffer: &[Pixel], width: usize, height: usize) -> io::Result<Vec<u8>> {
+    if width.checked_mul(height) != Some(buffer.len()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("buffer size does not match {}x{}", width, height),
+        ));
+    }
+    let (Ok(png_width), Ok(png_height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}x{} is too large for a png", width, height),
+        ));
+    };
+
+    let with_alpha = !buffer.iter().all(|&pixel| color::is_opaque(pixel));
+    let (color_type, channels) = if with_alpha {
+        (png::ColorType::Rgba, 4)
+    } else {
+        (png::ColorType::Rgb, 3)
+    };
+
+    let mut data = Vec::with_capacity(buffer.len() * channels);
+    for &pixel in buffer {
+        let (r, g, b) = color::channels(pixel);
+        data.extend_from_slice(&[r, g, b]);
+        if with_alpha {
+            data.push(color::alpha(pixel));
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, png_width, png_height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+pub fn save_png(
+    path: impl AsRef<Path>,
+    buffer: &[Pixel],
+    width: usize,
+    height: usize,
+) -> io::Result<()> {
+    fs::write(path, encode_png(buffer, width, height)?)
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<Image, DecodeError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(DecodeError::Unsupported("unexpanded palette".to_string()));
+        }
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in data.chunks(info.line_size).take(height) {
+        for px in row.chunks(channels).take(width) {
+            pixels.push(match channels {
+                1 => rgb(px[0], px[0], px[0]),
+                2 => rgba(px[0], px[0], px[0], px[1]),
+                3 => rgb(px[0], px[1], px[2]),
+                _ => rgba(px[0], px[1], px[2], px[3]),
+            });
+        }
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Decodes binary (`P6`) and ASCII (`P3`) PPM files.
+pub fn decode_ppm(bytes: &[u8]) -> Result<Image, DecodeError> {
+    let mut pos = 0;
+    let magic = ppm_token(bytes, &mut pos).ok_or_else(|| malformed("missing magic"))?;
+    let binary = match magic {
+        b"P6" => true,
+        b"P3" => false,
+        _ => return Err(DecodeError::UnknownFormat),
+    };
+    let mut header = [0usize; 3];
+    for value in header.iter_mut() {
+        *value = ppm_number(bytes, &mut pos)?;
+    }
+    let [width, height, max_value] = header;
+    if max_value == 0 || max_value > 65535 {
+        return Err(malformed("max value out of range"));
+    }
+    let count = width
+        .checked_mul(height)
+        .ok_or_else(|| malformed("image too large"))?;
+
+    let scale = |v: usize| -> Result<u8, DecodeError> {
+        if v > max_value {
+            return Err(malformed("sample exceeds max value"));
+        }
+        Ok((v * 255 / max_value) as u8)
+    };
+
+    let mut pixels = Vec::with_capacity(count.min(bytes.len()));
+    if binary {
+        // exactly one whitespace byte separates the header from the samples
+        pos += 1;
+        let sample_size = if max_value < 256 { 1 } else { 2 };
+        let data = bytes
+            .get(pos..)
+            .filter(|data| {
+                count
+                    .checked_mul(3 * sample_size)
+                    .is_some_and(|size| size <= data.len())
+            })
+            .ok_or_else(|| malformed("truncated pixel data"))?;
+        for px in data.chunks_exact(3 * sample_size).take(count) {
+            let sample = |i: usize| match sample_size {
+                1 => px[i] as usize,
+                _ => (px[i * 2] as usize) << 8 | px[i * 2 + 1] as usize,
+            };
+            pixels.push(rgb(scale(sample(0))?, scale(sample(1))?, scale(sample(2))?));
+        }
+    } else {
+        for _ in 0..count {
+            let r = scale(ppm_number(bytes, &mut pos)?)?;
+            let g = scale(ppm_number(bytes, &mut pos)?)?;
+            let b = scale(ppm_number(bytes, &mut pos)?)?;
+            pixels.push(rgb(r, g, b));
+        }
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn ppm_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        match bytes.get(*pos)? {
+            b'#' => {
+                while bytes.get(*pos).is_some_and(|&b| b != b'\n') {
+                    *pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while bytes
+        .get(*pos)
+        .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+    {
+        *pos += 1;
+    }
+    Some(&bytes[start..*pos])
+}
+
+fn ppm_number(bytes: &[u8], pos: &mut usize) -> Result<usize, DecodeError> {
+    let token = ppm_token(bytes, pos).ok_or_else(|| malformed("unexpected end of file"))?;
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| malformed("invalid number"))
+}
+
+/// Decodes uncompressed (`BI_RGB`) BMP files with 1, 4, 8, 24 or 32 bits per
+/// pixel.
+pub fn decode_bmp(bytes: &[u8]) -> Result<Image, DecodeError> {
+    let u16_at = |at: usize| -> Result<u16, DecodeError> {
+        bytes
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| malformed("truncated header"))
+    };
+    let u32_at = |at: usize| -> Result<u32, DecodeError> {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| malformed("truncated header"))
+    };
+
+    if !bytes.starts_with(b"BM") {
+        return Err(DecodeError::UnknownFormat);
+    }
+    let data_offset = u32_at(10)? as usize;
+    let header_size = u32_at(14)? as usize;
+    let (width, height, bits, compression, palette_entry) = match header_size {
+        12 => (
+            u16_at(18)? as i32,
+            u16_at(20)? as i16 as i32,
+            u16_at(24)?,
+            0,
+            3,
+        ),
+        40.. => (
+            u32_at(18)? as i32,
+            u32_at(22)? as i32,
+            u16_at(28)?,
+            u32_at(30)?,
+            4,
+        ),
+        _ => return Err(malformed("unknown header size")),
+    };
+    if compression != 0 {
+        return Err(DecodeError::Unsupported(format!(
+            "compression method {}",
+            compression
+        )));
+    }
+    if width <= 0 || height == 0 {
+        return Err(malformed("invalid dimensions"));
+    }
+    let top_down = height < 0;
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+
+    let palette = match bits {
+        1 | 4 | 8 => {
+            let used = if header_size >= 40 { u32_at(46)? } else { 0 };
+            let entries = if used == 0 { 1 << bits } else { used as usize };
+            let start = 14 + header_size;
+            let table = bytes
+                .get(start..start + entries * palette_entry)
+                .ok_or_else(|| malformed("truncated palette"))?;
+            table
+                .chunks_exact(palette_entry)
+                .map(|c| rgb(c[2], c[1], c[0]))
+                .collect()
+        }
+        24 | 32 => Vec::new(),
+        _ => {
+            return Err(DecodeError::Unsupported(format!("{} bits per pixel", bits)));
+        }
+    };
+
+    let row_size = (width * bits as usize).div_ceil(32) * 4;
+    let data = bytes
+        .get(data_offset..)
+        .filter(|data| {
+            row_size
+                .checked_mul(height)
+                .is_some_and(|size| size <= data.len())
+        })
+        .ok_or_else(|| malformed("truncated pixel data"))?;
+
+    let mut pixels = vec![0x000000; width * height];
+    for (i, row) in data.chunks_exact(row_size).take(height).enumerate() {
+        let y = if top_down { i } else { height - 1 - i };
+        for x in 0..width {
+            let pixel = match bits {
+                24 | 32 => {
+                    let at = x * bits as usize / 8;
+                    rgb(row[at + 2], row[at + 1], row[at])
+                }
+                _ => {
+                    let bit = x * bits as usize;
+                    let byte = row[bit / 8];
+                    let index = (byte >> (8 - bits as usize - bit % 8)) & ((1 << bits) - 1);
+                    *palette
+                        .get(index as usize)
+                        .ok_or_else(|| malformed("palette index out of range"))?
+                }
+            };
+            pixels[y * width + x] = pixel;
+        }
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm_ascii_and_binary() {
+        let ascii = b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n";
+        let image = Image::decode(ascii).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![0xFF0000, 0x0000FF]);
+
+        let mut binary = b"P6 1 2 15\n".to_vec();
+        binary.extend_from_slice(&[15, 0, 0, 0, 15, 15]);
+        let image = Image::decode(&binary).unwrap();
+        assert_eq!(image.pixels, vec![0xFF0000, 0x00FFFF]);
+    }
+
+    #[test]
+    fn bmp_24_bit_bottom_up() {
+        // 2x2, rows padded to 8 bytes, stored bottom row first
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&(54u32 + 16).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&54u32.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&2i32.to_le_bytes());
+        bmp.extend_from_slice(&2i32.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        bmp.extend_from_slice(&[0xFF, 0, 0, 0, 0xFF, 0, 0, 0]);
+        bmp.extend_from_slice(&[0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0]);
+
+        let image = Image::decode(&bmp).unwrap();
+        assert_eq!(image.pixels, vec![0xFF0000, 0xFFFFFF, 0x0000FF, 0x00FF00]);
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let pixels = vec![0x102030, 0x405060, 0x708090];
+        let image = Image::decode(&encode_png(&pixels, 3, 1).unwrap()).unwrap();
+        assert_eq!(image.pixels, pixels);
+
+        let pixels = vec![0x00102030, 0x80405060, 0xFF708090];
+        let image = Image::decode(&encode_png(&pixels, 3, 1).unwrap()).unwrap();
+        assert_eq!(image.pixels, pixels);
+    }
+
+    #[test]
+    fn bad_files_are_errors() {
+        assert!(matches!(
+            Image::decode(b"GIF89a"),
+            Err(DecodeError::UnknownFormat)
+        ));
+        assert!(matches!(
+            Image::decode(b"P6 4 4 255\n\x00\x00"),
+            Err(DecodeError::Malformed(_))
+        ));
+        assert!(matches!(
+            Image::decode(b"BM\x00\x00"),
+            Err(DecodeError::Malformed(_))
+        ));
+        assert!(Image::decode(b"\x89PNG\r\n\x1a\n").is_err());
+    }
+}
@@ -1,8 +1,6 @@
-use core::time;
+use std::ops::{Deref, DerefMut};
 use std::time::Instant;
 
-use ::nalgebra::Vector2;
-use font::get_font_5x7;
 use minifb::{Window, WindowOptions};
 
 pub mod minifb {
@@ -12,12 +10,44 @@ pub mod nalgebra {
     pub use nalgebra::*;
 }
 
+pub mod backend;
+pub mod blit;
+pub mod canvas;
 pub mod chart;
+pub mod color;
+pub mod curve;
+mod dash;
+mod dirty;
+mod ellipse;
+mod flood;
 pub mod font;
+pub mod image;
+pub mod paint;
+mod parallel;
+mod polygon;
+mod raster;
+pub mod rect;
+pub mod resample;
+mod rounded_rect;
 pub mod shapes;
+pub mod sprite;
+mod stroke;
+mod transform;
 
+pub use backend::{Backend, FileSequenceBackend, MemoryBackend, MinifbBackend};
+pub use blit::{BlitMode, BlitOptions};
+pub use canvas::{Canvas, Surface};
+pub use image::{DecodeError, Image, encode_png};
+pub use paint::{ColorStop, Paint};
+pub use rect::Rect;
+pub use resample::{Destination, Filter};
 pub use shapes::Shape;
+pub use sprite::{Animation, SpriteSheet};
 
+/// Packed 0xTTRRGGBB color. The top byte is transparency, i.e. inverted alpha:
+/// 0x00 is fully opaque, so plain 0x00RRGGBB colors stay opaque, and 0xFF is
+/// fully transparent. `color::rgba` and `color::alpha` convert from and to
+/// regular alpha values.
 pub type Pixel = u32;
 
 #[derive(Debug, Clone, Copy)]
@@ -27,359 +57,1274 @@ pub enum StrokeType {
     Center,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// How thick lines are joined where a polyline changes direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// Sharp corners, cut to a bevel when the miter would reach further than
+    /// `miter_limit` times the stroke width.
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How the open ends of thick lines are finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    /// Ends exactly at the end point.
+    Butt,
+    Round,
+    /// Extends past the end point by half the stroke width.
+    Square,
+}
+
+/// Decides which areas of a self-intersecting or nested polygon are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+impl FillRule {
+    pub fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// How drawn pixels combine with the pixels below them. Every mode works
+/// per channel and the result is then alpha-blended like `Normal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Source-over alpha blending.
+    Normal,
+    /// Darkens: `dst * src`.
+    Multiply,
+    /// Lightens: `1 - (1 - dst) * (1 - src)`.
+    Screen,
+    /// Sums the channels, clamped to white.
+    Add,
+    /// Multiplies dark and screens light areas of the destination.
+    Overlay,
+    /// `|dst - src|`; drawing white inverts.
+    Difference,
+}
+
+/// Which neighbours a flood fill spreads to: the four sharing an edge, or
+/// the diagonal ones as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+#[derive(Debug, Clone)]
 pub struct Stroke {
     pub color: Pixel,
     pub width: usize,
     pub stroke_type: StrokeType,
+    /// Alternating on and off lengths along the path; empty draws solid.
+    pub dash: Vec<f32>,
+    /// How far into the dash pattern the path starts.
+    pub dash_offset: f32,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+    pub miter_limit: f32,
 }
 
-pub struct Screen {
-    width: usize,
-    height: usize,
-    pub buffer: Vec<Pixel>,
-    pub window: Window,
-    stroke: Stroke,
-    fill: Pixel,
+impl Stroke {
+    pub fn new(color: Pixel, width: usize, stroke_type: StrokeType) -> Self {
+        Stroke {
+            color,
+            width,
+            stroke_type,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+            line_join: LineJoin::Round,
+            line_cap: LineCap::Round,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+pub struct Screen<B: Backend = MinifbBackend> {
+    canvas: Canvas,
+    pub backend: B,
     delta_time: f64,
     pub fps_estimate: f64,
     last_time: Instant,
+    /// Regions presented by the last `draw`.
+    presented: Vec<Rect>,
 }
 
-impl Screen {
+impl Screen<MinifbBackend> {
     pub fn new(width: usize, height: usize, window_name: &str, opts: WindowOptions) -> Self {
-        let buffer = vec![0x000000; width * height]; // Initialize with black color
-
-        let mut window = Window::new(window_name, width, height, opts).unwrap_or_else(|e| {
+        let backend = MinifbBackend::new(width, height, window_name, opts).unwrap_or_else(|e| {
             panic!("Window creation failed: {}", e);
         });
-        window.set_target_fps(60);
-
-        let stroke = Stroke {
-            color: 0xFFFFFF, // Default stroke color (white)
-            width: 1,        // Default stroke width
-            stroke_type: StrokeType::Center,
-        };
+        Screen::with_backend(width, height, backend)
+    }
 
-        let fill = 0x000000; // Default fill color (black)
+    pub fn window(&mut self) -> &mut Window {
+        &mut self.backend.window
+    }
+}
 
+impl<B: Backend> Screen<B> {
+    pub fn with_backend(width: usize, height: usize, backend: B) -> Self {
         Screen {
-            width,
-            height,
-            buffer,
-            window,
-            stroke,
-            fill,
+            canvas: Canvas::new(width, height),
+            backend,
             delta_time: 0.0,
             fps_estimate: 0.0,
             last_time: Instant::now(),
+            presented: Vec::new(),
         }
     }
 
-    pub fn stroke_color(&mut self, color: Pixel) {
-        self.stroke.color = color;
+    pub fn target_fps(&mut self, fps: usize) {
+        self.backend.set_target_fps(fps);
     }
-    pub fn stroke_width(&mut self, width: usize) {
-        self.stroke.width = width;
+
+    pub fn draw(&mut self) {
+        let (width, height) = (self.canvas.get_width(), self.canvas.get_height());
+        let regions = self.canvas.dirty_regions();
+        self.backend
+            .present_regions(&self.canvas.buffer, width, height, &regions)
+            .unwrap_or_else(|e| {
+                panic!("Presenting frame failed: {}", e);
+            });
+        self.canvas.reset_dirty();
+        self.presented = regions;
+        let current_time = Instant::now();
+        let elapsed = current_time.duration_since(self.last_time).as_secs_f64();
+        self.delta_time = elapsed;
+        self.last_time = current_time;
+        self.fps_estimate = 1.0 / elapsed;
     }
-    pub fn stroke_type(&mut self, s_type: StrokeType) {
-        self.stroke.stroke_type = s_type;
+
+    /// Fills what the last frame drew, and anything drawn since, with
+    /// `color`, leaving the rest of the buffer as it is. Cheaper than `solid`
+    /// when only small parts of the screen change between frames.
+    pub fn clear_dirty(&mut self, color: Pixel) {
+        let mut regions = std::mem::take(&mut self.presented);
+        regions.extend(self.canvas.dirty_regions());
+        for region in regions {
+            self.canvas.for_each_row(region, |_, row| row.fill(color));
+        }
     }
 
-    pub fn fill(&mut self, color: Pixel) {
-        self.fill = color;
+    /// Seconds between the last two calls to `draw`.
+    pub fn delta_time(&self) -> f64 {
+        self.delta_time
     }
 
-    pub fn target_fps(&mut self, fps: usize) {
-        self.window.set_target_fps(fps);
-    }
-
-    pub fn draw_shapes(&mut self, shapes: &[Box<dyn shapes::Shape>]) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let coordinates = Vector2::new(x, y);
-                for shape in shapes.iter().rev() {
-                    if let Some(color) = shape.is_in_shape(coordinates) {
-                        self.set_pixel(coordinates, color);
-                        break;
-                    }
-                }
-            }
-        }
-        println!("draw_shapes {}", shapes.len());
-    }
-
-    // FIX: check srtoke width
-    pub fn circle(&mut self, center: Vector2<usize>, radius: usize) {
-        let (inner_rad, outer_rad) = match self.stroke.stroke_type {
-            StrokeType::Inner => (
-                if radius < self.stroke.width {
-                    0
-                } else {
-                    radius - self.stroke.width
-                },
-                radius,
-            ),
-            StrokeType::Outer => (radius, radius + self.stroke.width),
-            StrokeType::Center => (
-                if radius < self.stroke.width / 2 {
-                    0
-                } else {
-                    radius - self.stroke.width / 2
-                },
-                radius + self.stroke.width / 2,
-            ),
-        };
-        let (inner_rad, outer_rad) = (inner_rad as isize, outer_rad as isize);
-        let inner_sq = inner_rad * inner_rad;
-        let outer_sq = outer_rad * outer_rad;
-
-        for y in -outer_rad..=outer_rad {
-            for x in -outer_rad..=outer_rad {
-                let point = Vector2::new(x, y);
-                let dist_sq = point.dot(&point);
-                if dist_sq <= outer_sq {
-                    let canvas_point = Vector2::new(center.x as isize + x, center.y as isize + y);
-                    let canvas_point = canvas_point.map(|v| v as usize);
-                    if dist_sq > inner_sq {
-                        self.set_pixel(canvas_point, self.stroke.color); // Draw the stroke
-                    } else {
-                        self.set_pixel(canvas_point, self.fill); // Fill the circle
-                    }
-                }
-            }
+    pub fn poll_events(&mut self) {
+        self.backend.poll_events();
+    }
+
+    pub fn is_window_open(&self) -> bool {
+        self.backend.is_open()
+    }
+
+    pub fn is_key_down(&self, key: minifb::Key) -> bool {
+        self.backend.is_key_down(key)
+    }
+}
+
+impl<B: Backend> Deref for Screen<B> {
+    type Target = Canvas;
+
+    fn deref(&self) -> &Canvas {
+        &self.canvas
+    }
+}
+
+impl<B: Backend> DerefMut for Screen<B> {
+    fn deref_mut(&mut self) -> &mut Canvas {
+        &mut self.canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+
+    use crate::chart::{Chart, ChartType};
+    use crate::shapes::{Circle, RoundedRect};
+    use crate::{
+        Animation, BlendMode, BlitMode, BlitOptions, Canvas, ColorStop, Connectivity, FillRule,
+        Filter, Image, LineCap, LineJoin, MemoryBackend, Paint, Pixel, Rect, Screen, Shape,
+        SpriteSheet, Stroke, StrokeType, Surface, color, curve, encode_png,
+    };
+
+    #[test]
+    fn circles() {
+        let mut canvas = Canvas::new(20, 20);
+        canvas.fill(0xFF0000);
+        canvas.stroke_color(0xFFFFFF);
+        canvas.stroke_width(2);
+        canvas.stroke_type(StrokeType::Inner);
+        canvas.circle(Vector2::new(10, 10), 5);
+
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0x000000));
+    }
+
+    #[test]
+    fn rects() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.fill(0x00FF00);
+        canvas.stroke_type(StrokeType::Inner);
+        canvas.rect(Vector2::new(2, 2), 4, 4, false);
+
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 2)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 3)), Some(0x00FF00));
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 6)), Some(0x000000));
+    }
+
+    #[test]
+    fn lines() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.line(Vector2::new(1, 1), Vector2::new(5, 1));
+
+        for x in 1..=5 {
+            assert_eq!(canvas.get_pixel(Vector2::new(x, 1)), Some(0xFFFFFF));
         }
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 1)), Some(0x000000));
     }
-    pub fn line(&mut self, start: Vector2<usize>, end: Vector2<usize>) {
-        // Bresenham's line algorithm
-        let (x0, y0) = (start.x, start.y);
-        let (x1, y1) = (end.x, end.y);
-
-        let dx = (x1 as isize - x0 as isize).abs();
-        let dy = (y1 as isize - y0 as isize).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
-
-        let mut x = x0;
-        let mut y = y0;
-
-        loop {
-            let position = Vector2::new(x, y);
-            if self.stroke.width == 1 {
-                self.set_pixel(position, self.stroke.color); // Draw a single pixel
-            } else {
-                let fill_color = self.fill;
-                self.fill(self.stroke.color); // Fill the stroke color
-                self.circle(position, self.stroke.width / 2); // Draw a circle for thickness
-                self.fill(fill_color); // Restore the fill color
-            }
-            if x == x1 && y == y1 {
-                break;
-            }
-            let err2 = err * 2;
-            if err2 > -dy {
-                err -= dy;
-                x = (x as isize + sx) as usize;
-            }
-            if err2 < dx {
-                err += dx;
-                y = (y as isize + sy) as usize;
-            }
+
+    #[test]
+    fn text_is_clipped_to_the_buffer() {
+        let mut canvas = Canvas::new(8, 8);
+        canvas.text(Vector2::new(4, 4), "AB", 2);
+
+        assert!(canvas.buffer.contains(&0xFFFFFF));
+    }
+
+    #[test]
+    fn render_loop_with_memory_backend() {
+        let mut screen = Screen::with_backend(4, 4, MemoryBackend::with_max_frames(3));
+        let mut frame = 0;
+        while screen.is_window_open() {
+            screen.solid(frame);
+            screen.draw();
+            frame += 1;
         }
+
+        assert_eq!(screen.backend.frames.len(), 3);
+        assert_eq!(screen.backend.last_frame(), Some(&[2; 16][..]));
+    }
+
+    #[test]
+    fn png_keeps_0rgb_channels() {
+        let buffer = [0x00FF8000, 0x000000FF, 0x00123456, 0x00FFFFFF];
+        let bytes = encode_png(&buffer, 2, 2).unwrap();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(
+            &data[..12],
+            &[
+                0xFF, 0x80, 0x00, 0x00, 0x00, 0xFF, 0x12, 0x34, 0x56, 0xFF, 0xFF, 0xFF
+            ]
+        );
+
+        let error = encode_png(&buffer, 3, 2).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
     }
 
-    pub fn solid(&mut self, color: Pixel) {
-        self.buffer.fill(color);
+    #[test]
+    fn translucent_fill_blends() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.solid(0x0000FF);
+        canvas.fill(color::rgba(255, 0, 0, 128));
+        canvas.stroke_width(0);
+        canvas.rect(Vector2::new(0, 0), 2, 2, false);
+
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0x80007F));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 3)), Some(0x0000FF));
+
+        canvas.fill(0x00FF00);
+        canvas.rect(Vector2::new(0, 0), 2, 2, false);
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0x00FF00));
     }
 
-    pub fn clear(&mut self) {
-        self.buffer.fill(0);
+    #[test]
+    fn antialiased_line_has_partial_coverage() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.antialias(true);
+        canvas.line(Vector2::new(0, 0), Vector2::new(9, 4));
+
+        let partial = canvas
+            .buffer
+            .iter()
+            .filter(|&&p| p != 0x000000 && p != 0xFFFFFF)
+            .count();
+        assert!(partial > 0);
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(9, 4)), Some(0xFFFFFF));
     }
 
-    pub fn is_in_bounds(&self, pos: Vector2<usize>) -> bool {
-        pos.x < self.width && pos.y < self.height
+    #[test]
+    fn antialiased_circle_keeps_solid_interior() {
+        let mut canvas = Canvas::new(21, 21);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(0);
+        canvas.antialias(true);
+        canvas.circle(Vector2::new(10, 10), 8);
+
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0x000000));
+        // the edge is partially covered red, with no stroke color mixed in
+        let (r, g, b) = color::channels(canvas.get_pixel(Vector2::new(16, 16)).unwrap());
+        assert!(
+            r > 0 && r < 0xFF && g == 0 && b == 0,
+            "{r:#x} {g:#x} {b:#x}"
+        );
     }
 
-    pub fn set_pixel(&mut self, pos: Vector2<usize>, pixel: Pixel) {
-        if self.is_in_bounds(pos) {
-            self.buffer[pos.y * self.width + pos.x] = pixel;
+    #[test]
+    fn primitives_clip_off_every_edge() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(3);
+        canvas.stroke_type(StrokeType::Outer);
+        canvas.rect(Vector2::new(0, 0), 4, 4, false);
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 4)), Some(0xFFFFFF));
+
+        canvas.clear();
+        canvas.stroke_width(1);
+        canvas.circle(Vector2::new(-3, -3), 5);
+        canvas.circle(Vector2::new(12, 12), 5);
+        canvas.line(Vector2::new(-20, 5), Vector2::new(30, 5));
+        canvas.text(Vector2::new(6, -12), "HI", 2);
+        canvas.draw_buffer(Vector2::new(-2, 8), &[0x00FF00; 16], 4, 4);
+
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(9, 9)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(5, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(-1, 0)), None);
+
+        // lines are clipped before stepping, so far-away end points are cheap
+        // and dashes still count from the real start
+        let (far_left, far_right) = (Vector2::new(-200_000_000, 5), Vector2::new(200_000_000, 5));
+        for antialias in [false, true] {
+            let mut canvas = Canvas::new(100, 100);
+            canvas.antialias(antialias);
+            canvas.line(far_left, far_right);
+            let lit = canvas.buffer.iter().filter(|&&p| p != 0x000000).count();
+            assert_eq!(lit, 100);
+            assert!((0..100).all(|x| canvas.get_pixel(Vector2::new(x, 5)) == Some(0xFFFFFF)));
         }
+        let mut canvas = Canvas::new(100, 100);
+        canvas.stroke_dash(&[4.0, 4.0], 0.0);
+        canvas.line(far_left, far_right);
+        let row: Vec<bool> = (0..12)
+            .map(|x| canvas.get_pixel(Vector2::new(x, 5)) == Some(0xFFFFFF))
+            .collect();
+        assert_eq!(
+            row,
+            [
+                true, true, true, true, false, false, false, false, true, true, true, true
+            ]
+        );
+    }
+
+    #[test]
+    fn clip_stack_nests_by_intersection() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.push_clip(Rect::new(2, 2, 6, 6));
+        canvas.push_clip(Rect::new(-5, -5, 10, 10));
+        assert_eq!(canvas.clip_rect(), Rect::new(2, 2, 3, 3));
+
+        canvas.solid(0x0000FF);
+        canvas.pop_clip();
+        canvas.line(Vector2::new(0, 6), Vector2::new(9, 6));
+        canvas.pop_clip();
+
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 4)), Some(0x0000FF));
+        assert_eq!(canvas.get_pixel(Vector2::new(5, 5)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 6)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 6)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(8, 6)), Some(0x000000));
+        assert_eq!(canvas.clip_rect(), Rect::new(0, 0, 10, 10));
+    }
+
+    #[test]
+    fn transform_stack_places_primitives() {
+        let mut canvas = Canvas::new(12, 12);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(0);
+
+        canvas.push_transform();
+        canvas.translate(2.0, 3.0);
+        canvas.rect(Vector2::new(0, 0), 2, 2, false);
+        canvas.pop_transform();
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 3)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 4)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 4)), Some(0x000000));
+
+        canvas.clear();
+        canvas.push_transform();
+        canvas.translate(10.0, 0.0);
+        canvas.rotate(std::f32::consts::FRAC_PI_2);
+        canvas.rect(Vector2::new(0, 0), 4, 2, false);
+        canvas.pop_transform();
+        assert_eq!(canvas.get_pixel(Vector2::new(8, 1)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(9, 3)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 1)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(7, 1)), Some(0x000000));
+
+        canvas.clear();
+        canvas.scale(2.0, 2.0);
+        canvas.circle(Vector2::new(2, 2), 1);
+        assert_eq!(canvas.get_pixel(Vector2::new(5, 5)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(5, 8)), Some(0x000000));
     }
 
-    pub fn text(&mut self, pos: Vector2<usize>, text: &str, scale: usize) {
-        let (x, y) = (pos.x, pos.y);
-        let font = get_font_5x7();
-        let char_width = 5;
-        let _char_height = 7;
-        let spacing = 1;
-
-        for (i, c) in text.chars().enumerate() {
-            if let Some(bitmap) = font.get(&c) {
-                let x_offset = x + i * (char_width + spacing) * scale;
-
-                for (row, row_bits) in bitmap.iter().enumerate() {
-                    for col in 0..char_width {
-                        if (row_bits >> (char_width - 1 - col)) & 1 == 1 {
-                            let px = x_offset + col * scale;
-                            let py = y + row * scale;
-                            let fill_color = self.fill;
-                            let stroke_width = self.stroke.width;
-                            self.fill(self.stroke.color); // Fill the stroke color
-                            self.stroke_width(0);
-                            self.rect(Vector2::new(px, py), scale, scale, false);
-                            self.fill(fill_color); // Restore the fill color
-                            self.stroke_width(stroke_width);
-                        }
-                    }
+    #[test]
+    fn polygon_fill_rules_and_stroke() {
+        // pentagram: the center is enclosed twice
+        let star: Vec<Vector2<f32>> = (0..5)
+            .map(|i| {
+                let angle = i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+                Vector2::new(10.0 + 9.0 * angle.sin(), 10.0 - 9.0 * angle.cos())
+            })
+            .collect();
+        let mut canvas = Canvas::new(20, 20);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(0);
+        canvas.polygon(&star);
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0xFF0000));
+
+        canvas.clear();
+        canvas.fill_rule(FillRule::EvenOdd);
+        canvas.polygon(&star);
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 3)), Some(0xFF0000));
+
+        canvas.clear();
+        canvas.stroke_width(2);
+        canvas.stroke_type(StrokeType::Inner);
+        let square = [
+            Vector2::new(2.0, 2.0),
+            Vector2::new(10.0, 2.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(2.0, 10.0),
+        ];
+        canvas.polygon(&square);
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 5)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 5)), Some(0x000000));
+
+        canvas.clear();
+        canvas.stroke_type(StrokeType::Outer);
+        canvas.polygon(&square);
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 5)), Some(0xFF0000));
+    }
+
+    #[test]
+    fn bezier_curves() {
+        let flat = curve::flatten_quad(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(50.0, 0.0),
+            Vector2::new(100.0, 0.0),
+            curve::FLATNESS,
+        );
+        let bent = curve::flatten_quad(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(50.0, 100.0),
+            Vector2::new(100.0, 0.0),
+            curve::FLATNESS,
+        );
+        assert_eq!(flat.len(), 2);
+        assert!(bent.len() > 8);
+
+        let mut canvas = Canvas::new(20, 20);
+        canvas.quad_bezier(
+            Vector2::new(0.5, 5.5),
+            Vector2::new(10.0, 5.5),
+            Vector2::new(19.5, 5.5),
+        );
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(19, 5)), Some(0xFFFFFF));
+
+        canvas.clear();
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(0);
+        canvas.cubic_bezier(
+            Vector2::new(10.0, 2.0),
+            Vector2::new(-5.0, 22.0),
+            Vector2::new(25.0, 22.0),
+            Vector2::new(10.0, 2.0),
+        );
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 2)), Some(0x000000));
+    }
+
+    #[test]
+    fn ellipses_arcs_and_pies() {
+        let mut canvas = Canvas::new(21, 21);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(0);
+        canvas.ellipse(Vector2::new(10, 10), 6, 3);
+        assert_eq!(canvas.get_pixel(Vector2::new(15, 10)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 12)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 14)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(17, 10)), Some(0x000000));
+
+        canvas.clear();
+        canvas.stroke_width(2);
+        canvas.stroke_type(StrokeType::Inner);
+        canvas.arc(Vector2::new(10, 10), 6, 0.0, std::f32::consts::FRAC_PI_2);
+        assert_eq!(canvas.get_pixel(Vector2::new(14, 14)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 14)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0x000000));
+
+        canvas.clear();
+        canvas.stroke_width(0);
+        canvas.pie(Vector2::new(10, 10), 6, 0.0, std::f32::consts::FRAC_PI_2);
+        assert_eq!(canvas.get_pixel(Vector2::new(12, 12)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(8, 8)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(12, 8)), Some(0x000000));
+    }
+
+    #[test]
+    fn rounded_rects() {
+        let mut canvas = Canvas::new(20, 20);
+        canvas.fill(0xFF0000);
+        canvas.stroke_width(1);
+        canvas.stroke_type(StrokeType::Inner);
+        canvas.rounded_rect(Vector2::new(2, 2), 16, 10, [6, 0, 0, 0], false);
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 2)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(17, 2)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 2)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 6)), Some(0xFF0000));
+
+        let stroke = Stroke::new(0xFFFFFF, 1, StrokeType::Inner);
+        let shape = RoundedRect::new(
+            Vector2::new(2, 2),
+            16,
+            10,
+            [6, 0, 0, 0],
+            Some(0x00FF00),
+            stroke,
+        );
+        let mut shapes_canvas = Canvas::new(20, 20);
+        shapes_canvas.draw_shapes(&[Box::new(shape)]);
+
+        canvas.clear();
+        canvas.fill(0x00FF00);
+        canvas.rounded_rect(Vector2::new(2, 2), 16, 10, [6, 0, 0, 0], false);
+        assert_eq!(shapes_canvas.buffer, canvas.buffer);
+    }
+
+    #[test]
+    fn gradient_fills() {
+        let stops = [ColorStop::new(0.0, 0x000000), ColorStop::new(1.0, 0xFF0000)];
+        let mut canvas = Canvas::new(20, 20);
+        canvas.stroke_width(0);
+        canvas.fill_paint(Paint::linear(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 0.0),
+            &stops,
+        ));
+        canvas.rect(Vector2::new(0, 0), 10, 4, false);
+        let red = |canvas: &Canvas, x: isize, y: isize| {
+            color::channels(canvas.get_pixel(Vector2::new(x, y)).unwrap()).0
+        };
+        assert!(red(&canvas, 0, 0) < 0x20);
+        assert!(red(&canvas, 0, 0) < red(&canvas, 5, 0) && red(&canvas, 5, 0) < red(&canvas, 9, 0));
+        assert!(red(&canvas, 9, 0) > 0xE0);
+        assert_eq!(red(&canvas, 5, 0), red(&canvas, 5, 3));
+
+        // the gradient moves with the transform
+        let reference = canvas.buffer.clone();
+        canvas.clear();
+        canvas.translate(0.0, 10.0);
+        canvas.rect(Vector2::new(0, 0), 10, 4, false);
+        assert_eq!(&canvas.buffer[200..280], &reference[..80]);
+        canvas.reset_transform();
+
+        // radial and conic gradients start from their first stop
+        canvas.clear();
+        canvas.fill_paint(Paint::radial(Vector2::new(10.5, 10.5), 8.0, &stops));
+        canvas.circle(Vector2::new(10, 10), 8);
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0x000000));
+        assert!(red(&canvas, 10, 17) > 0xC0);
+
+        let conic = Paint::conic(Vector2::zeros(), 0.0, &stops);
+        assert_eq!(conic.color_at(Vector2::new(1.0, 0.001)), 0x000000);
+        assert!(color::channels(conic.color_at(Vector2::new(1.0, -0.001))).0 > 0xF0);
+
+        let mut polygon_canvas = Canvas::new(20, 20);
+        polygon_canvas.stroke_width(0);
+        polygon_canvas.fill_paint(Paint::linear(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 0.0),
+            &stops,
+        ));
+        polygon_canvas.polygon(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 0.0),
+            Vector2::new(10.0, 4.0),
+            Vector2::new(0.0, 4.0),
+        ]);
+        assert_eq!(polygon_canvas.buffer, reference);
+    }
+
+    #[test]
+    fn chart_area_fill() {
+        let mut chart = Chart::new(20, 20, (0.0, 10.0), (0.0, 10.0));
+        chart.fill(0x00FF00);
+        chart.draw(ChartType::Area, &[(0.0, 5.0), (10.0, 5.0)]);
+        let pixel = |x: usize, y: usize| chart.buffer[y * 20 + x];
+        assert_eq!(pixel(5, 15), 0x00FF00);
+        assert_eq!(pixel(5, 10), 0xFFFFFF);
+        assert_eq!(pixel(5, 5), 0x000000);
+    }
+
+    #[test]
+    fn dashed_strokes() {
+        let mut canvas = Canvas::new(20, 20);
+        canvas.stroke_dash(&[4.0, 4.0], 0.0);
+        canvas.line(Vector2::new(0, 5), Vector2::new(19, 5));
+        let row: Vec<bool> = (0..20)
+            .map(|x| canvas.get_pixel(Vector2::new(x, 5)) == Some(0xFFFFFF))
+            .collect();
+        assert_eq!(
+            &row[..12],
+            &[
+                true, true, true, true, false, false, false, false, true, true, true, true
+            ]
+        );
+
+        // the offset shifts the pattern along the line
+        canvas.clear();
+        canvas.stroke_dash(&[4.0, 4.0], 2.0);
+        canvas.line(Vector2::new(0, 5), Vector2::new(19, 5));
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 5)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 5)), Some(0x000000));
+
+        // rect outlines follow the perimeter clockwise from the top-left
+        canvas.clear();
+        canvas.stroke_dash(&[3.0, 3.0], 0.0);
+        canvas.stroke_type(StrokeType::Inner);
+        canvas.rect(Vector2::new(2, 2), 12, 12, true);
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 2)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 2)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(8, 2)), Some(0xFFFFFF));
+
+        // circle outlines have gaps but keep their fill
+        let stroke_pixels =
+            |canvas: &Canvas| canvas.buffer.iter().filter(|&&p| p == 0xFFFFFF).count();
+        canvas.clear();
+        canvas.fill(0xFF0000);
+        canvas.stroke_dash(&[], 0.0);
+        canvas.circle(Vector2::new(10, 10), 8);
+        let solid = stroke_pixels(&canvas);
+        canvas.clear();
+        canvas.stroke_dash(&[2.0, 2.0], 0.0);
+        canvas.circle(Vector2::new(10, 10), 8);
+        let dashed = stroke_pixels(&canvas);
+        assert!(dashed > solid / 4 && dashed < solid * 3 / 4);
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 10)), Some(0xFF0000));
+
+        // patterns too fine to see draw solid instead of hanging
+        let points = [Vector2::new(1.0, 3.0), Vector2::new(19.0, 15.0)];
+        for antialias in [false, true] {
+            let draw = |pattern: &[f32]| {
+                let mut canvas = Canvas::new(20, 20);
+                canvas.antialias(antialias);
+                canvas.stroke_dash(pattern, 0.0);
+                for width in [1, 3] {
+                    canvas.stroke_width(width);
+                    canvas.polyline(&points);
                 }
-            }
+                canvas.buffer
+            };
+            assert!(draw(&[1e-7, 1e-7]) == draw(&[]));
         }
-    }
 
-    pub fn rect(&mut self, pos: Vector2<usize>, width: usize, height: usize, only_stroke: bool) {
-        let mut pos = pos;
-        let (outer_width, outer_height, inner_width, outer_heigth) = match self.stroke.stroke_type {
-            StrokeType::Inner => (
-                width,
-                height,
-                if width < self.stroke.width * 2 {
-                    0
-                } else {
-                    width - self.stroke.width * 2
-                },
-                if height < self.stroke.width * 2 {
-                    0
-                } else {
-                    height - self.stroke.width * 2
-                },
-            ),
-            StrokeType::Outer => {
-                pos.x -= self.stroke.width;
-                pos.y -= self.stroke.width;
-                (
-                    width + self.stroke.width * 2,
-                    height + self.stroke.width * 2,
-                    width,
-                    height,
-                )
-            }
-            StrokeType::Center => {
-                pos.x -= self.stroke.width / 2;
-                pos.y -= self.stroke.width / 2;
-                (
-                    width + self.stroke.width,
-                    height + self.stroke.width,
-                    if width < self.stroke.width {
-                        0
-                    } else {
-                        width - self.stroke.width
-                    },
-                    if height < self.stroke.width {
-                        0
-                    } else {
-                        height - self.stroke.width
-                    },
-                )
+        // runs far along a path still advance
+        let mut stroke = Stroke::new(0xFFFFFF, 1, StrokeType::Center);
+        stroke.dash = vec![1.0, 1.0];
+        let runs = stroke.dash_runs(1e6, 1e6 + 10.0);
+        assert_eq!(runs.len(), 5);
+        assert_eq!(runs[0], (1e6, 1e6 + 1.0));
+
+        // offsets that are not finite count as 0, huge ones wrap into the pattern
+        let mut stroke = Stroke::new(0xFFFFFF, 3, StrokeType::Center);
+        stroke.dash = vec![2.0, 3.0];
+        for offset in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 1e30] {
+            stroke.dash_offset = offset;
+            let runs = stroke.dash_runs(0.0, 10.0);
+            assert!(!runs.is_empty() && runs.len() <= 3, "{offset}: {runs:?}");
+            if !offset.is_finite() {
+                assert_eq!(runs, [(0.0, 2.0), (5.0, 7.0)]);
             }
-        };
-        for dy in 0..outer_height {
-            for dx in 0..outer_width {
-                let position = Vector2::new(pos.x + dx, pos.y + dy);
-                match self.stroke.stroke_type {
-                    StrokeType::Inner => {
-                        if dx >= self.stroke.width
-                            && dx < outer_width - self.stroke.width
-                            && dy >= self.stroke.width
-                            && dy < outer_height - self.stroke.width
-                        {
-                            if !only_stroke {
-                                self.set_pixel(position, self.fill);
-                            } // Fill the inner rectangle
-                        } else {
-                            self.set_pixel(position, self.stroke.color); // Draw the stroke
-                        }
-                    }
-                    StrokeType::Outer => {
-                        if dx < self.stroke.width
-                            || dx >= outer_width - self.stroke.width
-                            || dy < self.stroke.width
-                            || dy >= outer_height - self.stroke.width
-                        {
-                            self.set_pixel(position, self.stroke.color); // Draw the stroke
-                        } else if !only_stroke {
-                            self.set_pixel(position, self.fill);
-                        }
-                    }
-                    StrokeType::Center => {
-                        if dx < self.stroke.width
-                            || dx >= outer_width - self.stroke.width
-                            || dy < self.stroke.width
-                            || dy >= outer_height - self.stroke.width
-                        {
-                            self.set_pixel(position, self.stroke.color); // Draw the stroke
-                        } else if !only_stroke {
-                            self.set_pixel(position, self.fill);
-                        }
-                    }
+            for antialias in [false, true] {
+                for width in [1, 3] {
+                    let mut canvas = Canvas::new(20, 20);
+                    canvas.antialias(antialias);
+                    canvas.stroke_width(width);
+                    canvas.stroke_dash(&[2.0, 3.0], offset);
+                    canvas.line(Vector2::new(0, 10), Vector2::new(19, 10));
+                    assert!(canvas.buffer.iter().any(|&p| p != 0x000000), "{offset}");
                 }
             }
         }
+
+        let mut chart = Chart::new(20, 20, (0.0, 10.0), (-10.0, 10.0));
+        chart.chart_color(0x000000);
+        chart.stroke_dash(&[5.0, 5.0], 0.0);
+        chart.draw(ChartType::Lines, &[(0.0, 5.0), (5.0, 5.0), (10.0, 5.0)]);
+        let row: Vec<bool> = (0..20)
+            .map(|x| chart.buffer[5 * 20 + x] == 0xFFFFFF)
+            .collect();
+        assert!(row[..5].iter().all(|&on| on));
+        assert!(row[5..10].iter().all(|&on| !on));
+        assert!(row[10..15].iter().all(|&on| on));
     }
 
-    pub fn draw_buffer(
-        &mut self,
-        pos: Vector2<usize>,
-        buffer: &[Pixel],
-        width: usize,
-        height: usize,
-    ) {
-        let (x0, y0) = (pos.x, pos.y);
-        // draw buffer
-        for y in 0..height {
-            for x in 0..width {
-                let pixel = buffer[y * width + x];
-                self.set_pixel(Vector2::new(x + x0, y + y0), pixel);
-            }
+    #[test]
+    fn polyline_joins_and_caps() {
+        let corner = [
+            Vector2::new(4.0, 10.0),
+            Vector2::new(14.0, 10.0),
+            Vector2::new(14.0, 20.0),
+        ];
+        let outer_corner = Vector2::new(16, 7);
+        let mut canvas = Canvas::new(24, 24);
+        canvas.stroke_width(6);
+        for (join, expected) in [
+            (LineJoin::Miter, 0xFFFFFF),
+            (LineJoin::Bevel, 0x000000),
+            (LineJoin::Round, 0x000000),
+        ] {
+            canvas.clear();
+            canvas.line_join(join);
+            canvas.polyline(&corner);
+            assert_eq!(canvas.get_pixel(outer_corner), Some(expected), "{join:?}");
+            assert_eq!(canvas.get_pixel(Vector2::new(14, 10)), Some(0xFFFFFF));
+        }
+        // a low miter limit turns the corner into a bevel
+        canvas.clear();
+        canvas.line_join(LineJoin::Miter);
+        canvas.miter_limit(1.2);
+        canvas.polyline(&corner);
+        assert_eq!(canvas.get_pixel(outer_corner), Some(0x000000));
+
+        for (cap, expected) in [
+            (LineCap::Butt, 0x000000),
+            (LineCap::Square, 0xFFFFFF),
+            (LineCap::Round, 0xFFFFFF),
+        ] {
+            canvas.clear();
+            canvas.line_cap(cap);
+            canvas.polyline(&corner);
+            assert_eq!(
+                canvas.get_pixel(Vector2::new(2, 10)),
+                Some(expected),
+                "{cap:?}"
+            );
         }
-        // draw outline
-        let fill = self.fill;
-        self.fill(self.stroke.color);
-        self.rect(Vector2::new(x0, y0), width, height, true);
-        self.fill(fill);
+
+        // overlapping pieces are painted once
+        canvas.clear();
+        canvas.stroke_color(color::rgba(255, 255, 255, 128));
+        canvas.line_join(LineJoin::Round);
+        canvas.polyline(&corner);
+        let segment = canvas.get_pixel(Vector2::new(8, 10));
+        assert_ne!(segment, Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(14, 10)), segment);
+        assert_eq!(canvas.get_pixel(Vector2::new(14, 16)), segment);
+
+        // thick lines are stroked with the same geometry
+        canvas.clear();
+        canvas.stroke_color(0xFFFFFF);
+        canvas.stroke_width(4);
+        canvas.line_cap(LineCap::Butt);
+        canvas.line(Vector2::new(4, 10), Vector2::new(17, 10));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 11)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 13)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 10)), Some(0x000000));
+
+        canvas.clear();
+        canvas.stroke_dash(&[4.0, 4.0], 0.0);
+        canvas.line(Vector2::new(4, 10), Vector2::new(17, 10));
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 11)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(10, 11)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(14, 11)), Some(0xFFFFFF));
+
+        // zero width draws a hairline through either entry point
+        let mut line = Canvas::new(20, 20);
+        line.stroke_width(0);
+        line.line(Vector2::new(2, 3), Vector2::new(9, 3));
+        let mut polyline = Canvas::new(20, 20);
+        polyline.stroke_width(0);
+        polyline.polyline(&[Vector2::new(2.5, 3.5), Vector2::new(9.5, 3.5)]);
+        assert_eq!(line.get_pixel(Vector2::new(5, 3)), Some(0xFFFFFF));
+        assert!(line.buffer == polyline.buffer);
     }
 
-    pub fn draw(&mut self) {
-        self.window
-            .update_with_buffer(&self.buffer, self.width, self.height)
-            .unwrap();
-        let current_time = Instant::now();
-        let elapsed = current_time.duration_since(self.last_time).as_secs_f64();
-        self.delta_time = elapsed;
-        self.last_time = current_time;
-        self.fps_estimate = 1.0 / elapsed;
+    #[test]
+    fn flood_fill() {
+        // a diagonal wall splits the canvas for 4-connectivity only
+        let mut canvas = Canvas::new(10, 10);
+        canvas.line(Vector2::new(0, 9), Vector2::new(9, 0));
+        canvas.flood_fill(Vector2::new(0, 0), 0xFF0000);
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 3)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(9, 9)), Some(0x000000));
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 9)), Some(0xFFFFFF));
+
+        canvas.clear();
+        canvas.line(Vector2::new(0, 9), Vector2::new(9, 0));
+        canvas.flood_fill_with(Vector2::new(0, 0), 0x0000FF, Connectivity::Eight, 0);
+        assert_eq!(canvas.get_pixel(Vector2::new(9, 9)), Some(0x0000FF));
+
+        // tolerance lets the fill spread over similar colors
+        let mut canvas = Canvas::new(10, 1);
+        for x in 0..10 {
+            canvas.set_pixel(Vector2::new(x, 0), color::rgb(x as u8 * 10, 0, 0));
+        }
+        canvas.flood_fill_with(Vector2::new(0, 0), 0xFFFFFF, Connectivity::Four, 45);
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 0)), Some(0xFFFFFF));
+        assert_eq!(
+            canvas.get_pixel(Vector2::new(5, 0)),
+            Some(color::rgb(50, 0, 0))
+        );
+
+        // the fill stops at the clip, and big regions do not recurse
+        let mut canvas = Canvas::new(500, 500);
+        canvas.push_clip(Rect::new(0, 0, 500, 250));
+        canvas.flood_fill(Vector2::new(10, 10), 0x00FF00);
+        canvas.pop_clip();
+        assert_eq!(canvas.get_pixel(Vector2::new(499, 249)), Some(0x00FF00));
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 250)), Some(0x000000));
     }
 
-    pub fn is_window_open(&self) -> bool {
-        self.window.is_open()
+    #[test]
+    fn blit_modes_flips_and_tint() {
+        // 3x2 sprite: magenta key color on the left column
+        let mut sprite = Image::new(3, 2);
+        sprite.pixels = vec![0xFF00FF, 0x00FF00, 0x0000FF, 0xFF00FF, 0xFFFF00, 0xFFFFFF];
+
+        let mut canvas = Canvas::new(10, 10);
+        canvas.solid(0x101010);
+        let keyed = BlitOptions {
+            mode: BlitMode::ColorKey(0xFF00FF),
+            ..BlitOptions::default()
+        };
+        canvas.blit(&sprite, Rect::new(0, 0, 3, 2), Vector2::new(2, 2), &keyed);
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 2)), Some(0x101010));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 2)), Some(0x00FF00));
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 3)), Some(0xFFFFFF));
+        // no outline unless asked for
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 1)), Some(0x101010));
+
+        let flipped = BlitOptions {
+            flip_x: true,
+            flip_y: true,
+            ..keyed
+        };
+        canvas.blit(&sprite, Rect::new(1, 0, 2, 2), Vector2::new(6, 6), &flipped);
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 6)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(7, 7)), Some(0x00FF00));
+
+        // tint multiplies, alpha mode blends
+        let tinted = BlitOptions {
+            tint: Some(color::rgba(255, 0, 0, 128)),
+            ..BlitOptions::default()
+        };
+        canvas.solid(0x000000);
+        canvas.blit(&sprite, Rect::new(2, 1, 1, 1), Vector2::new(0, 0), &tinted);
+        assert_eq!(
+            canvas.get_pixel(Vector2::new(0, 0)),
+            Some(color::rgb(128, 0, 0))
+        );
+
+        // draw_buffer keeps its outline
+        let outlined = BlitOptions {
+            outline: true,
+            mode: BlitMode::Opaque,
+            ..BlitOptions::default()
+        };
+        canvas.solid(0x000000);
+        canvas.stroke_type(StrokeType::Outer);
+        canvas.blit(
+            &sprite,
+            Rect::new(0, 0, 3, 2),
+            Vector2::new(2, 2),
+            &outlined,
+        );
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 1)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(2, 2)), Some(0xFF00FF));
+        let mut reference = Canvas::new(10, 10);
+        reference.stroke_type(StrokeType::Outer);
+        reference.draw_buffer(Vector2::new(2, 2), &sprite.pixels, 3, 2);
+        assert_eq!(reference.buffer, canvas.buffer);
+
+        // a source rect reaching past the image keeps the image in place
+        let mut pair = Image::new(2, 1);
+        pair.pixels = vec![0x0000AA, 0x0000BB];
+        let row = |canvas: &Canvas| -> Vec<Pixel> {
+            (2..5)
+                .map(|x| canvas.get_pixel(Vector2::new(x, 0)).unwrap())
+                .collect()
+        };
+        let mut canvas = Canvas::new(6, 1);
+        let src = Rect::new(-1, 0, 3, 1);
+        canvas.blit(&pair, src, Vector2::new(2, 0), &BlitOptions::default());
+        assert_eq!(row(&canvas), [0x000000, 0x0000AA, 0x0000BB]);
+        canvas.clear();
+        let flipped = BlitOptions {
+            flip_x: true,
+            ..BlitOptions::default()
+        };
+        canvas.blit(&pair, src, Vector2::new(2, 0), &flipped);
+        assert_eq!(row(&canvas), [0x0000BB, 0x0000AA, 0x000000]);
     }
 
-    pub fn is_key_down(&self, key: minifb::Key) -> bool {
-        self.window.is_key_down(key)
+    #[test]
+    fn sprite_sheets_and_animation() {
+        // 4x2 sheet of 2x2 frames, each frame a single color
+        let colors = [0x110000, 0x002200];
+        let mut image = Image::new(4, 2);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            *pixel = colors[(i % 4) / 2];
+        }
+        let mut sheet = SpriteSheet::grid(image, 2, 2);
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.frame(1), Some(Rect::new(2, 0, 2, 2)));
+        sheet.name_frame("walk", 1);
+        let idle = sheet.add_frame("idle", Rect::new(0, 0, 1, 1));
+        assert_eq!(sheet.index_of("walk"), Some(1));
+        assert_eq!(sheet.named("idle"), Some(Rect::new(0, 0, 1, 1)));
+        assert_eq!(idle, 2);
+        assert_eq!(sheet.frame_image(1).unwrap().pixels, vec![0x002200; 4]);
+
+        let mut animation = Animation::new(vec![0, 1], 10.0);
+        assert_eq!(animation.current_frame(), Some(0));
+        animation.update(0.15);
+        assert_eq!(animation.current_frame(), Some(1));
+        animation.update(0.1);
+        assert_eq!(animation.current_frame(), Some(0));
+
+        let mut once = Animation::new(vec![0, 1], 10.0).once();
+        once.update(1.0);
+        assert_eq!(once.current_frame(), Some(1));
+        assert!(once.is_finished());
+
+        let mut screen = Screen::with_backend(4, 4, MemoryBackend::with_max_frames(2));
+        screen.draw();
+        animation.update(screen.delta_time());
+        screen.draw_animation(
+            &sheet,
+            &animation,
+            Vector2::new(1, 1),
+            &BlitOptions::default(),
+        );
+        assert_eq!(screen.get_pixel(Vector2::new(2, 2)), Some(0x110000));
     }
 
-    pub fn get_width(&self) -> usize {
-        self.width
+    #[test]
+    fn scaled_and_rotated_images() {
+        let mut image = Image::new(2, 2);
+        image.pixels = vec![0xFF0000, 0x00FF00, 0x0000FF, 0xFFFFFF];
+
+        // nearest: each image pixel becomes a 3x3 block
+        let mut canvas = Canvas::new(8, 8);
+        canvas.draw_image_transformed(&image, Rect::new(1, 1, 6, 6), Filter::Nearest);
+        assert_eq!(canvas.get_pixel(Vector2::new(1, 1)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(3, 3)), Some(0xFF0000));
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 1)), Some(0x00FF00));
+        assert_eq!(canvas.get_pixel(Vector2::new(6, 6)), Some(0xFFFFFF));
+        assert_eq!(canvas.get_pixel(Vector2::new(7, 7)), Some(0x000000));
+
+        // bilinear blends between neighbours and keeps the corners
+        let mut canvas = Canvas::new(8, 8);
+        canvas.draw_image_transformed(&image, Rect::new(0, 0, 8, 8), Filter::Bilinear);
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 0)), Some(0xFF0000));
+        let (r, g, _) = color::channels(canvas.get_pixel(Vector2::new(4, 0)).unwrap());
+        assert!(r > 0x40 && g > 0x40);
+
+        // a matrix destination combined with the canvas transform, without holes
+        let mut canvas = Canvas::new(20, 20);
+        canvas.translate(10.0, 10.0);
+        canvas.rotate(std::f32::consts::FRAC_PI_4);
+        let solid = Image {
+            width: 4,
+            height: 4,
+            pixels: vec![0x00FF00; 16],
+        };
+        let centered = nalgebra::Matrix3::new_scaling(2.0)
+            * nalgebra::Matrix3::new_translation(&Vector2::new(-2.0, -2.0));
+        canvas.draw_image_transformed(&solid, centered, Filter::Nearest);
+        for (x, y) in [(10, 10), (10, 5), (14, 10), (8, 12)] {
+            assert_eq!(
+                canvas.get_pixel(Vector2::new(x, y)),
+                Some(0x00FF00),
+                "{x},{y}"
+            );
+        }
+        assert_eq!(canvas.get_pixel(Vector2::new(4, 4)), Some(0x000000));
     }
 
-    pub fn get_height(&self) -> usize {
-        self.height
+    #[test]
+    fn surfaces_composite_onto_the_screen() {
+        let mut layer = Surface::new(4, 4);
+        layer.solid(color::rgba(0, 0, 0, 0));
+        layer.set_pixel(Vector2::new(1, 1), 0xFF0000);
+
+        let mut screen = Screen::with_backend(8, 8, MemoryBackend::new());
+        screen.solid(0x0000FF);
+        screen.composite(&layer, Vector2::new(2, 2), 1.0, BlendMode::Normal);
+        assert_eq!(screen.get_pixel(Vector2::new(3, 3)), Some(0xFF0000));
+        // transparent layer pixels leave the screen alone
+        assert_eq!(screen.get_pixel(Vector2::new(2, 2)), Some(0x0000FF));
+
+        screen.solid(0x000000);
+        screen.composite(&layer, Vector2::new(2, 2), 0.5, BlendMode::Normal);
+        assert_eq!(
+            screen.get_pixel(Vector2::new(3, 3)),
+            Some(color::rgb(128, 0, 0))
+        );
+
+        // charts render into a surface of their own
+        let mut chart = Chart::new(4, 4, (0.0, 4.0), (0.0, 4.0));
+        chart.chart_color(0x000000);
+        chart.draw(ChartType::Dots, &[(1.0, 3.0)]);
+        screen.solid(0x000000);
+        screen.composite(&chart, Vector2::new(4, 4), 1.0, BlendMode::Normal);
+        assert_eq!(screen.get_pixel(Vector2::new(5, 5)), Some(0xFFFFFF));
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn blend_modes() {
+        let dst = color::rgb(200, 100, 0);
+        let src = color::rgb(100, 100, 255);
+        let cases = [
+            (BlendMode::Normal, src),
+            (BlendMode::Multiply, color::rgb(78, 39, 0)),
+            (BlendMode::Screen, color::rgb(222, 161, 255)),
+            (BlendMode::Add, color::rgb(255, 200, 255)),
+            (BlendMode::Overlay, color::rgb(188, 78, 0)),
+            (BlendMode::Difference, color::rgb(100, 0, 255)),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(color::blend_with(dst, src, mode), expected, "{mode:?}");
+
+            // set_pixel and draw_buffer use the canvas blend mode
+            let mut canvas = Canvas::new(2, 1);
+            canvas.solid(dst);
+            canvas.blend_mode(mode);
+            canvas.set_pixel(Vector2::new(0, 0), src);
+            canvas.stroke_width(0);
+            canvas.draw_buffer(Vector2::new(1, 0), &[src], 1, 1);
+            assert_eq!(canvas.buffer, vec![expected; 2], "{mode:?}");
+        }
+
+        // half-transparent sources mix the blended color in halfway
+        let half = color::with_alpha(0xFFFFFF, 128);
+        let inverted = color::blend_with(color::rgb(255, 0, 0), half, BlendMode::Difference);
+        assert_eq!(inverted, color::rgb(127, 128, 128));
+
+        // compositing uses the mode passed in, not the canvas one
+        let mut layer = Surface::new(1, 1);
+        layer.solid(src);
+        let mut canvas = Canvas::new(1, 1);
+        canvas.solid(dst);
+        canvas.composite(&layer, Vector2::new(0, 0), 1.0, BlendMode::Add);
+        assert_eq!(canvas.buffer, vec![color::rgb(255, 200, 255)]);
+    }
+
+    #[test]
+    fn draw_shapes_topmost_wins_within_bounds() {
+        /// Horizontal band with no bounding box.
+        struct Band(isize, Pixel);
+        impl Shape for Band {
+            fn is_in_shape(&self, pos: Vector2<isize>) -> Option<Pixel> {
+                (pos.y == self.0).then_some(self.1)
+            }
+            fn set_pos(&mut self, pos: Vector2<isize>) {
+                self.0 = pos.y;
+            }
+        }
+
+        let stroke = Stroke::new(color::rgba(255, 255, 255, 128), 2, StrokeType::Center);
+        let shapes: Vec<Box<dyn Shape>> = vec![
+            Box::new(Band(10, 0x00FF00)),
+            Box::new(Circle::new(
+                Vector2::new(8, 8),
+                5,
+                Some(0xFF0000),
+                stroke.clone(),
+            )),
+            Box::new(Circle::new(Vector2::new(13, 10), 4, None, stroke.clone())),
+            Box::new(RoundedRect::new(
+                Vector2::new(-3, 14),
+                10,
+                8,
+                [3; 4],
+                Some(0x0000FF),
+                stroke,
+            )),
+        ];
+        let mut canvas = Canvas::new(20, 20);
+        canvas.solid(0x202020);
+        canvas.draw_shapes(&shapes);
+
+        // every pixel shows the topmost shape covering it
+        for y in 0..20 {
+            for x in 0..20 {
+                let pos = Vector2::new(x, y);
+                let expected = shapes
+                    .iter()
+                    .rev()
+                    .find_map(|shape| shape.is_in_shape(pos))
+                    .map_or(0x202020, |pixel| color::blend(0x202020, pixel));
+                assert_eq!(canvas.get_pixel(pos), Some(expected), "{x},{y}");
+            }
+        }
+        assert_eq!(canvas.get_pixel(Vector2::new(0, 10)), Some(0x00FF00));
+    }
+
+    #[test]
+    fn parallel_mode_matches_single_thread() {
+        let stroke = Stroke::new(color::rgba(255, 255, 255, 128), 3, StrokeType::Center);
+        let shapes: Vec<Box<dyn Shape + Sync>> = vec![
+            Box::new(Circle::new(
+                Vector2::new(120, 120),
+                100,
+                Some(0xFF0000),
+                stroke.clone(),
+            )),
+            Box::new(RoundedRect::new(
+                Vector2::new(150, 120),
+                220,
+                160,
+                [25; 4],
+                Some(0x0000FF),
+                stroke,
+            )),
+        ];
+        let mut image = Image::new(3, 3);
+        image.pixels = (0..9)
+            .map(|i| color::rgba(i * 28, 255 - i * 28, 80, 200))
+            .collect();
+
+        // large enough for every step to be split across threads
+        let render = |threads: usize| {
+            let mut canvas = Canvas::new(400, 300);
+            canvas.parallel(threads);
+            canvas.solid(0x202020);
+            canvas.push_clip(Rect::new(10, 10, 380, 280));
+            canvas.solid(0x102030);
+            canvas.draw_shapes_sync(&shapes);
+            canvas.stroke_dash(&[4.0, 2.0], 1.0);
+            canvas.fill_paint(Paint::radial(
+                Vector2::new(200.0, 100.0),
+                90.0,
+                &[ColorStop::new(0.0, 0xFFFF00), ColorStop::new(1.0, 0x00FFFF)],
+            ));
+            canvas.blend_mode(BlendMode::Multiply);
+            canvas.rect(Vector2::new(20, 15), 360, 270, false);
+            canvas.blend_mode(BlendMode::Normal);
+            canvas.translate(200.0, 150.0);
+            canvas.rotate(0.4);
+            canvas.draw_image_transformed(
+                &image,
+                Rect::new(-150, -150, 300, 300),
+                Filter::Bilinear,
+            );
+            canvas.buffer
+        };
+        let single = render(1);
+        for threads in [2, 3, 7, 300] {
+            assert!(render(threads) == single, "{threads} threads");
+        }
+
+        // small areas are not worth a thread
+        let mut canvas = Canvas::new(400, 300);
+        canvas.parallel(8);
+        let caller = std::thread::current().id();
+        canvas.for_each_row(Rect::new(0, 0, 200, 100), |_, _| {
+            assert_eq!(std::thread::current().id(), caller);
+        });
+
+        // the sync variant draws what draw_shapes draws
+        let mut canvas = Canvas::new(400, 300);
+        canvas.parallel(4);
+        canvas.draw_shapes_sync(&shapes);
+        let mut expected = Canvas::new(400, 300);
+        let shapes: Vec<Box<dyn Shape>> = shapes
+            .into_iter()
+            .map(|shape| shape as Box<dyn Shape>)
+            .collect();
+        expected.draw_shapes(&shapes);
+        assert!(canvas.buffer == expected.buffer);
+    }
 
     #[test]
-    fn circles() {}
+    fn dirty_regions_follow_drawing() {
+        let mut screen = Screen::with_backend(64, 40, MemoryBackend::new());
+        assert_eq!(screen.dirty_regions(), vec![Rect::new(0, 0, 64, 40)]);
+        screen.draw();
+        assert_eq!(screen.dirty_regions(), vec![]);
+
+        screen.set_pixel(Vector2::new(20, 20), 0xFF0000);
+        screen.stroke_width(0);
+        screen.fill(0x00FF00);
+        screen.rect(Vector2::new(40, 2), 20, 4, false);
+        let changed = vec![Rect::new(32, 0, 32, 16), Rect::new(16, 16, 16, 16)];
+        assert_eq!(screen.dirty_regions(), changed);
+        screen.draw();
+        assert_eq!(screen.backend.regions[0], vec![Rect::new(0, 0, 64, 40)]);
+        assert_eq!(screen.backend.regions[1], changed);
+
+        // clearing only what the last frame drew is enough here
+        screen.clear_dirty(0x000000);
+        assert!(screen.buffer.iter().all(|&pixel| pixel == 0x000000));
+        assert_eq!(screen.dirty_regions(), changed);
+
+        // tiles in a column merge, separate columns do not
+        let mut canvas = Canvas::new(64, 40);
+        canvas.reset_dirty();
+        for y in [1, 17, 33] {
+            canvas.set_pixel(Vector2::new(1, y), 0xFFFFFF);
+        }
+        canvas.set_pixel(Vector2::new(33, 1), 0xFFFFFF);
+        assert_eq!(
+            canvas.dirty_regions(),
+            vec![Rect::new(0, 0, 16, 40), Rect::new(32, 0, 16, 16)]
+        );
+        canvas.mark_dirty();
+        assert_eq!(canvas.dirty_regions(), vec![Rect::new(0, 0, 64, 40)]);
+
+        // shapes only dirty the tiles their bounding boxes reach
+        let stroke = Stroke::new(0xFFFFFF, 1, StrokeType::Center);
+        let dot: Vec<Box<dyn Shape + Sync>> = vec![Box::new(Circle::new(
+            Vector2::new(24, 24),
+            5,
+            Some(0xFF0000),
+            stroke,
+        ))];
+        let mut canvas = Canvas::new(256, 256);
+        canvas.reset_dirty();
+        canvas.draw_shapes_sync(&dot);
+        assert_eq!(canvas.dirty_regions(), vec![Rect::new(16, 16, 16, 16)]);
+    }
 }
@@ -0,0 +1,195 @@
+use std::f32::consts::TAU;
+
+use nalgebra::{Matrix3, Point2, Vector2};
+
+use crate::{Canvas, Pixel, color};
+
+/// Color at an `offset` in `0.0..=1.0` along a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub color: Pixel,
+}
+
+impl ColorStop {
+    pub fn new(offset: f32, color: Pixel) -> Self {
+        ColorStop { offset, color }
+    }
+}
+
+/// How an area is filled. Gradient geometry is given in the same coordinates
+/// as the shapes being drawn, so it follows the current transform.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint {
+    Solid(Pixel),
+    /// Colors change along the line from `start` to `end`.
+    LinearGradient {
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        stops: Vec<ColorStop>,
+    },
+    /// Colors change with the distance from `center`, reaching the last stop
+    /// at `radius`.
+    RadialGradient {
+        center: Vector2<f32>,
+        radius: f32,
+        stops: Vec<ColorStop>,
+    },
+    /// Colors sweep clockwise around `center`, starting at `angle` radians.
+    ConicGradient {
+        center: Vector2<f32>,
+        angle: f32,
+        stops: Vec<ColorStop>,
+    },
+}
+
+impl Paint {
+    pub fn linear(start: Vector2<f32>, end: Vector2<f32>, stops: &[ColorStop]) -> Self {
+        Paint::LinearGradient {
+            start,
+            end,
+            stops: sorted(stops),
+        }
+    }
+
+    pub fn radial(center: Vector2<f32>, radius: f32, stops: &[ColorStop]) -> Self {
+        Paint::RadialGradient {
+            center,
+            radius,
+            stops: sorted(stops),
+        }
+    }
+
+    pub fn conic(center: Vector2<f32>, angle: f32, stops: &[ColorStop]) -> Self {
+        Paint::ConicGradient {
+            center,
+            angle,
+            stops: sorted(stops),
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        matches!(self, Paint::Solid(_))
+    }
+
+    /// Color of the paint at `pos`.
+    pub fn color_at(&self, pos: Vector2<f32>) -> Pixel {
+        match self {
+            Paint::Solid(color) => *color,
+            Paint::LinearGradient { start, end, stops } => {
+                let direction = end - start;
+                let length_sq = direction.norm_squared();
+                let t = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    (pos - start).dot(&direction) / length_sq
+                };
+                sample(stops, t)
+            }
+            Paint::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                let t = if *radius > 0.0 {
+                    (pos - center).norm() / radius
+                } else {
+                    1.0
+                };
+                sample(stops, t)
+            }
+            Paint::ConicGradient {
+                center,
+                angle,
+                stops,
+            } => {
+                let offset = pos - center;
+                let t = (offset.y.atan2(offset.x) - angle).rem_euclid(TAU) / TAU;
+                sample(stops, t)
+            }
+        }
+    }
+}
+
+impl From<Pixel> for Paint {
+    fn from(color: Pixel) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+fn sorted(stops: &[ColorStop]) -> Vec<ColorStop> {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    stops
+}
+
+/// Interpolates between the stops around `t`; the end colors extend past
+/// the first and last stop.
+fn sample(stops: &[ColorStop], t: f32) -> Pixel {
+    let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+        return 0x000000;
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+    if t >= last.offset {
+        return last.color;
+    }
+    for pair in stops.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if t <= to.offset {
+            let span = to.offset - from.offset;
+            let amount = if span > 0.0 {
+                (t - from.offset) / span
+            } else {
+                1.0
+            };
+            return color::lerp(from.color, to.color, amount);
+        }
+    }
+    last.color
+}
+
+/// A paint ready to be sampled at screen pixels, which are mapped back to
+/// the coordinates the shape was drawn in.
+pub(crate) struct PaintSampler {
+    paint: Paint,
+    to_local: Option<Matrix3<f32>>,
+}
+
+impl PaintSampler {
+    pub(crate) fn color_at(&self, x: isize, y: isize) -> Pixel {
+        if let Paint::Solid(color) = self.paint {
+            return color;
+        }
+        let center = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+        let pos = match &self.to_local {
+            Some(inverse) => inverse.transform_point(&center),
+            None => center,
+        };
+        self.paint.color_at(pos.coords)
+    }
+}
+
+impl Canvas {
+    /// Fills shapes with `paint`; `fill` is the shorthand for a solid color.
+    pub fn fill_paint(&mut self, paint: Paint) {
+        self.fill = paint;
+    }
+
+    pub(crate) fn sampler(&self, paint: &Paint) -> PaintSampler {
+        let to_local = if paint.is_solid() || self.transform == Matrix3::identity() {
+            None
+        } else {
+            self.transform.try_inverse()
+        };
+        PaintSampler {
+            paint: paint.clone(),
+            to_local,
+        }
+    }
+
+    pub(crate) fn fill_sampler(&self) -> PaintSampler {
+        self.sampler(&self.fill)
+    }
+}
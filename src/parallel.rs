@@ -0,0 +1,90 @@
+//! Opt-in multithreading. Work that only depends on the pixel being written
+//! is split into bands of rows rendered on scoped threads, which gives the
+//! same output as rendering the rows one after another.
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+use nalgebra::Vector2;
+
+use crate::{Canvas, Pixel, Rect, color, shapes::Shape};
+
+/// Areas with fewer pixels than this are rendered on the calling thread;
+/// spawning threads for them costs more than it saves.
+const PARALLEL_MIN_PIXELS: usize = 64 * 1024;
+
+impl Canvas {
+    /// Renders per-pixel work (`solid`, rect fills, `draw_shapes_sync`,
+    /// `draw_image_transformed`) on `threads` threads; `0` uses one per core
+    /// and `1`, the default, stays on the calling thread.
+    pub fn parallel(&mut self, threads: usize) {
+        self.threads = if threads == 0 {
+            thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        } else {
+            threads
+        };
+    }
+
+    /// Calls `render(y, row)` for every row of `area`, clipped, where `row`
+    /// holds the pixels from `area.x` to `area.right()`. In parallel mode,
+    /// large areas are split into bands of rows rendered on separate threads.
+    pub(crate) fn for_each_row(&mut self, area: Rect, render: impl Fn(isize, &mut [Pixel]) + Sync) {
+        let area = area.intersect(&self.clip);
+        if area.is_empty() {
+            return;
+        }
+        self.dirty.mark_rect(area);
+        let width = self.get_width();
+        let (left, right) = (area.x as usize, area.right() as usize);
+        let rows = &mut self.buffer[area.y as usize * width..area.bottom() as usize * width];
+        let render_band = |first_row: isize, band: &mut [Pixel]| {
+            for (i, row) in band.chunks_mut(width).enumerate() {
+                render(first_row + i as isize, &mut row[left..right]);
+            }
+        };
+
+        let threads = self.threads.min(area.height);
+        if threads <= 1 || area.width * area.height < PARALLEL_MIN_PIXELS {
+            render_band(area.y, rows);
+            return;
+        }
+        let band_rows = area.height.div_ceil(threads);
+        thread::scope(|scope| {
+            for (i, band) in rows.chunks_mut(band_rows * width).enumerate() {
+                let render_band = &render_band;
+                scope.spawn(move || render_band(area.y + (i * band_rows) as isize, band));
+            }
+        });
+    }
+
+    /// `draw_shapes` for shapes that can be shared between threads, which
+    /// lets it use the parallel mode.
+    pub fn draw_shapes_sync(&mut self, shapes: &[Box<dyn Shape + Sync>]) {
+        let bounds: Vec<Option<Rect>> = shapes.iter().map(|shape| shape.bounding_box()).collect();
+        let mode = self.blend_mode;
+        // only rows and columns some shape can reach, which also keeps the
+        // dirty area small; unbounded shapes may cover the whole clip
+        let area = bounds
+            .iter()
+            .try_fold(Rect::default(), |area, b| b.map(|b| area.union(&b)))
+            .unwrap_or(self.clip)
+            .intersect(&self.clip);
+        self.for_each_row(area, |y, row| {
+            // shapes reaching this row, topmost first
+            let candidates: Vec<usize> = (0..shapes.len())
+                .rev()
+                .filter(|&i| bounds[i].is_none_or(|b| y >= b.y && y < b.bottom()))
+                .collect();
+            for (i, pixel) in row.iter_mut().enumerate() {
+                let pos = Vector2::new(area.x + i as isize, y);
+                let hit = candidates
+                    .iter()
+                    .filter(|&&s| bounds[s].is_none_or(|b| b.contains(pos)))
+                    .find_map(|&s| shapes[s].is_in_shape(pos));
+                if let Some(color) = hit {
+                    *pixel = color::blend_with(*pixel, color, mode);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,98 @@
+use nalgebra::Vector2;
+
+use crate::{Canvas, FillRule, Rect, StrokeType, color, raster};
+
+impl Canvas {
+    pub fn fill_rule(&mut self, rule: FillRule) {
+        self.fill_rule = rule;
+    }
+
+    /// Closed polygon through `points`, filled with the current fill using the
+    /// current `FillRule` and outlined with the current stroke. Points are in
+    /// pixel-edge coordinates: `(0, 0)`-`(2, 2)` covers exactly four pixels.
+    pub fn polygon(&mut self, points: &[Vector2<f32>]) {
+        if points.len() < 3 {
+            return;
+        }
+        let contour = points.iter().map(|&p| self.transform_point(p)).collect();
+        self.paint_outline(&[contour], false);
+    }
+
+    /// Fills and strokes screen-space contours. The stroke is the band of
+    /// pixels within the stroke width of an edge, kept inside, outside or
+    /// centered on the outline according to the `StrokeType`.
+    pub(crate) fn paint_outline(&mut self, contours: &[Vec<Vector2<f32>>], only_stroke: bool) {
+        let width = self.stroke.width as f32 * self.transform_scale();
+        // signed distances (negative inside) covered by the stroke
+        let (band_from, band_to) = match self.stroke.stroke_type {
+            StrokeType::Inner => (-width, 0.0),
+            StrokeType::Outer => (0.0, width),
+            StrokeType::Center => (-width / 2.0, width / 2.0),
+        };
+        let reach = band_from.abs().max(band_to) + 1.0;
+
+        let Some((min, max)) = raster::bounds(contours.iter().flatten().copied()) else {
+            return;
+        };
+        let area = Rect::from_corners(
+            min.map(|v| (v - reach).floor() as isize),
+            max.map(|v| (v + reach).ceil() as isize),
+        )
+        .intersect(&self.clip);
+        if area.is_empty() {
+            return;
+        }
+
+        let mut inside = vec![0.0f32; area.width * area.height];
+        let rule = self.fill_rule;
+        raster::fill_polygon(
+            contours,
+            area,
+            self.antialias,
+            |winding| rule.is_inside(winding),
+            |x, y, coverage| {
+                inside[(y - area.y) as usize * area.width + (x - area.x) as usize] = coverage;
+            },
+        );
+        let distances = if width > 0.0 {
+            raster::distance_field(contours, area, reach)
+        } else {
+            Vec::new()
+        };
+
+        let antialias = self.antialias;
+        let coverage = |distance: f32| {
+            let c = raster::edge_coverage(distance);
+            if antialias { c } else { c.round() }
+        };
+        let (fill, stroke) = (self.fill_sampler(), self.stroke.color);
+        for (i, &inside_coverage) in inside.iter().enumerate() {
+            let (fill_coverage, stroke_coverage) = if width > 0.0 {
+                let distance = distances[i];
+                let signed = if inside_coverage >= 0.5 {
+                    -distance
+                } else {
+                    distance
+                };
+                let outer = coverage(signed - band_to);
+                let inner = coverage(signed - band_from);
+                (inner, outer - inner)
+            } else {
+                (inside_coverage, 0.0)
+            };
+            let fill_coverage = if only_stroke { 0.0 } else { fill_coverage };
+
+            let total = fill_coverage + stroke_coverage;
+            if total > 0.0 {
+                let x = area.x + (i % area.width) as isize;
+                let y = area.y + (i / area.width) as isize;
+                let pixel = if fill_coverage > 0.0 {
+                    color::lerp(fill.color_at(x, y), stroke, stroke_coverage / total)
+                } else {
+                    stroke
+                };
+                self.plot(x, y, pixel, total);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Coverage-producing rasterizers used by `Canvas`. Each one
+//! calls `plot(x, y, coverage)` with a coverage in `0.0..=1.0` and leaves the
+//! actual pixel writing to the caller.
+
+use nalgebra::Vector2;
+
+use crate::Rect;
+
+/// Coverage of a pixel whose center lies `distance` outside an edge
+/// (negative when inside).
+pub(crate) fn edge_coverage(distance: f32) -> f32 {
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+/// Xiaolin Wu's anti-aliased one pixel wide line.
+pub(crate) fn wu_line(
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    mut plot: impl FnMut(isize, isize, f32),
+) {
+    let steep = (end.y - start.y).abs() > (end.x - start.x).abs();
+    let (mut a, mut b) = if steep {
+        (start.yx(), end.yx())
+    } else {
+        (start, end)
+    };
+    if a.x > b.x {
+        std::mem::swap(&mut a, &mut b);
+    }
+
+    let mut emit = |major: isize, minor: isize, coverage: f32| {
+        if coverage > 0.0 {
+            if steep {
+                plot(minor, major, coverage);
+            } else {
+                plot(major, minor, coverage);
+            }
+        }
+    };
+
+    let dx = b.x - a.x;
+    let gradient = if dx == 0.0 { 1.0 } else { (b.y - a.y) / dx };
+    let x_start = a.x.round();
+    let x_end = b.x.round();
+    let mut y = a.y + gradient * (x_start - a.x);
+
+    let mut x = x_start;
+    while x <= x_end {
+        let fract = y - y.floor();
+        emit(x as isize, y.floor() as isize, 1.0 - fract);
+        emit(x as isize, y.floor() as isize + 1, fract);
+        y += gradient;
+        x += 1.0;
+    }
+}
+
+/// Liang–Barsky clipping: the part of the segment `start`-`end` inside the
+/// box `min`-`max`, as a range of the parameter `t` along the segment. Works
+/// in `f64` so far-away end points still clip precisely.
+pub(crate) fn clip_segment(
+    start: Vector2<f64>,
+    end: Vector2<f64>,
+    min: Vector2<f64>,
+    max: Vector2<f64>,
+) -> Option<(f64, f64)> {
+    let delta = end - start;
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-delta.x, start.x - min.x),
+        (delta.x, max.x - start.x),
+        (-delta.y, start.y - min.y),
+        (delta.y, max.y - start.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+pub(crate) fn distance_to_segment(point: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.norm_squared();
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        ((point - a).dot(&ab) / length_sq).clamp(0.0, 1.0)
+    };
+    (point - (a + ab * t)).norm()
+}
+
+/// Corners of the smallest box holding all `points`, or `None` when there
+/// are no points or the box is not finite.
+pub(crate) fn bounds(
+    points: impl IntoIterator<Item = Vector2<f32>>,
+) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    let mut min = Vector2::repeat(f32::INFINITY);
+    let mut max = Vector2::repeat(f32::NEG_INFINITY);
+    for point in points {
+        min = min.inf(&point);
+        max = max.sup(&point);
+    }
+    (min.x.is_finite() && min.y.is_finite() && max.x.is_finite() && max.y.is_finite())
+        .then_some((min, max))
+}
+
+/// Scanline fill of one or more closed contours. Pixel centers sit at
+/// `(x + 0.5, y + 0.5)`; `inside` tells from a winding number whether a span
+/// is filled, so the caller picks the fill rule. Anti-aliasing samples four
+/// sub-scanlines and computes exact horizontal coverage.
+pub(crate) fn fill_polygon(
+    contours: &[Vec<Vector2<f32>>],
+    clip: Rect,
+    antialias: bool,
+    inside: impl Fn(i32) -> bool,
+    mut plot: impl FnMut(isize, isize, f32),
+) {
+    let Some((min, max)) = bounds(contours.iter().flatten().copied()) else {
+        return;
+    };
+    let x0 = (min.x.floor() as isize).max(clip.x);
+    let x1 = (max.x.ceil() as isize).min(clip.right());
+    let y0 = (min.y.floor() as isize).max(clip.y);
+    let y1 = (max.y.ceil() as isize).min(clip.bottom());
+    if x0 >= x1 || y0 >= y1 {
+        return;
+    }
+
+    let samples = if antialias { 4 } else { 1 };
+    let weight = 1.0 / samples as f32;
+    let mut coverage = vec![0.0f32; (x1 - x0) as usize];
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+    for y in y0..y1 {
+        coverage.fill(0.0);
+        for sample in 0..samples {
+            let sample_y = y as f32 + (sample as f32 + 0.5) * weight;
+            crossings.clear();
+            for contour in contours {
+                for (i, a) in contour.iter().enumerate() {
+                    let b = contour[(i + 1) % contour.len()];
+                    if (a.y <= sample_y) != (b.y <= sample_y) {
+                        let x = a.x + (sample_y - a.y) * (b.x - a.x) / (b.y - a.y);
+                        crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+                    }
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if inside(winding) {
+                    add_span(&mut coverage, x0, pair[0].0, pair[1].0, weight, antialias);
+                }
+            }
+        }
+        for (i, &c) in coverage.iter().enumerate() {
+            if c > 0.0 {
+                plot(x0 + i as isize, y, c.min(1.0));
+            }
+        }
+    }
+}
+
+fn add_span(coverage: &mut [f32], x0: isize, from: f32, to: f32, weight: f32, antialias: bool) {
+    let len = coverage.len() as isize;
+    if antialias {
+        let first = (from.floor() as isize - x0).max(0);
+        let last = (to.ceil() as isize - x0).min(len);
+        for i in first..last {
+            let left = (x0 + i) as f32;
+            let overlap = to.min(left + 1.0) - from.max(left);
+            if overlap > 0.0 {
+                coverage[i as usize] += overlap * weight;
+            }
+        }
+    } else {
+        // pixels whose center lies in `from..to`
+        let first = ((from - 0.5).ceil() as isize - x0).max(0);
+        let last = ((to - 0.5).ceil() as isize - x0).min(len);
+        for i in first..last {
+            coverage[i as usize] += weight;
+        }
+    }
+}
+
+/// Distance from every pixel center in `area` to the nearest contour edge,
+/// row-major. Pixels farther than `reach` from every edge stay at infinity.
+pub(crate) fn distance_field(contours: &[Vec<Vector2<f32>>], area: Rect, reach: f32) -> Vec<f32> {
+    let mut field = vec![f32::INFINITY; area.width * area.height];
+    for contour in contours {
+        for (i, &a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            let y_from = ((a.y.min(b.y) - reach).floor() as isize).max(area.y);
+            let y_to = ((a.y.max(b.y) + reach).ceil() as isize).min(area.bottom());
+            for y in y_from..y_to {
+                let center_y = y as f32 + 0.5;
+                // x extent of the segment part within `reach` of this row
+                let (x_min, x_max) = if (b.y - a.y).abs() < f32::EPSILON {
+                    (a.x.min(b.x), a.x.max(b.x))
+                } else {
+                    let t = |y: f32| ((y - a.y) / (b.y - a.y)).clamp(0.0, 1.0);
+                    let x1 = a.x + t(center_y - reach) * (b.x - a.x);
+                    let x2 = a.x + t(center_y + reach) * (b.x - a.x);
+                    (x1.min(x2), x1.max(x2))
+                };
+                let x_from = ((x_min - reach).floor() as isize).max(area.x);
+                let x_to = ((x_max + reach).ceil() as isize).min(area.right());
+                for x in x_from..x_to {
+                    let center = Vector2::new(x as f32 + 0.5, center_y);
+                    let distance = distance_to_segment(center, a, b);
+                    let index = (y - area.y) as usize * area.width + (x - area.x) as usize;
+                    if distance < field[index] {
+                        field[index] = distance;
+                    }
+                }
+            }
+        }
+    }
+    field
+}
@@ -0,0 +1,76 @@
+use nalgebra::Vector2;
+
+/// Axis-aligned rectangle in buffer coordinates. `x`/`y` are the top-left
+/// corner and may be negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: isize,
+    pub y: isize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: isize, y: isize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Rectangle spanning from `min` (inclusive) to `max` (exclusive).
+    pub fn from_corners(min: Vector2<isize>, max: Vector2<isize>) -> Self {
+        Rect {
+            x: min.x,
+            y: min.y,
+            width: (max.x - min.x).max(0) as usize,
+            height: (max.y - min.y).max(0) as usize,
+        }
+    }
+
+    pub fn right(&self) -> isize {
+        self.x + self.width as isize
+    }
+
+    pub fn bottom(&self) -> isize {
+        self.y + self.height as isize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, pos: Vector2<isize>) -> bool {
+        pos.x >= self.x && pos.x < self.right() && pos.y >= self.y && pos.y < self.bottom()
+    }
+
+    /// Overlapping area of both rectangles, empty if they do not touch.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        Rect::from_corners(
+            Vector2::new(self.x.max(other.x), self.y.max(other.y)),
+            Vector2::new(
+                self.right().min(other.right()),
+                self.bottom().min(other.bottom()),
+            ),
+        )
+    }
+
+    /// Smallest rectangle containing both; empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        Rect::from_corners(
+            Vector2::new(self.x.min(other.x), self.y.min(other.y)),
+            Vector2::new(
+                self.right().max(other.right()),
+                self.bottom().max(other.bottom()),
+            ),
+        )
+    }
+}
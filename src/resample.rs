@@ -0,0 +1,132 @@
+//! Drawing images scaled, rotated or otherwise transformed. Every covered
+//! canvas pixel is mapped back into the image and sampled there, so the
+//! output has no holes whatever the transform.
+
+use nalgebra::{Matrix3, Point2, Vector2};
+
+use crate::{Canvas, Image, Pixel, Rect, color, raster};
+
+/// How an image is sampled between its pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// Where a transformed image is drawn, in local coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Destination {
+    /// Stretches the whole image over the rect.
+    Rect(Rect),
+    /// Maps image pixel coordinates (pixel edges, `(0, 0)` being the
+    /// top-left corner of the image) to local coordinates.
+    Matrix(Matrix3<f32>),
+}
+
+impl From<Rect> for Destination {
+    fn from(rect: Rect) -> Self {
+        Destination::Rect(rect)
+    }
+}
+
+impl From<Matrix3<f32>> for Destination {
+    fn from(matrix: Matrix3<f32>) -> Self {
+        Destination::Matrix(matrix)
+    }
+}
+
+impl Canvas {
+    /// Draws `image` mapped onto `destination` and then through the current
+    /// transform, blending by the image's alpha.
+    pub fn draw_image_transformed(
+        &mut self,
+        image: &Image,
+        destination: impl Into<Destination>,
+        filter: Filter,
+    ) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        let (width, height) = (image.width as f32, image.height as f32);
+        let local = match destination.into() {
+            Destination::Rect(rect) => {
+                Matrix3::new_translation(&Vector2::new(rect.x as f32, rect.y as f32))
+                    * Matrix3::new_nonuniform_scaling(&Vector2::new(
+                        rect.width as f32 / width,
+                        rect.height as f32 / height,
+                    ))
+            }
+            Destination::Matrix(matrix) => matrix,
+        };
+        let to_screen = self.transform * local;
+        let Some(to_image) = to_screen.try_inverse() else {
+            return;
+        };
+
+        let corners = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)].map(|corner| {
+            to_screen
+                .transform_point(&Point2::new(corner.0, corner.1))
+                .coords
+        });
+        let Some((min, max)) = raster::bounds(corners) else {
+            return;
+        };
+        let area = Rect::from_corners(
+            min.map(|v| v.floor() as isize),
+            max.map(|v| v.ceil() as isize),
+        )
+        .intersect(&self.clip);
+        let mode = self.blend_mode;
+        self.for_each_row(area, |y, row| {
+            for (i, pixel) in row.iter_mut().enumerate() {
+                let center = Point2::new((area.x + i as isize) as f32 + 0.5, y as f32 + 0.5);
+                let p = to_image.transform_point(&center).coords;
+                if p.x < 0.0 || p.y < 0.0 || p.x >= width || p.y >= height {
+                    continue;
+                }
+                let color = match filter {
+                    Filter::Nearest => image.pixels[p.y as usize * image.width + p.x as usize],
+                    Filter::Bilinear => bilinear(image, p),
+                };
+                *pixel = color::blend_with(*pixel, color, mode);
+            }
+        });
+    }
+}
+
+/// Interpolates the four pixels around `p`, weighting colors by alpha so
+/// transparent pixels do not darken their neighbours.
+fn bilinear(image: &Image, p: Vector2<f32>) -> Pixel {
+    let (sx, sy) = (p.x - 0.5, p.y - 0.5);
+    let (fx, fy) = (sx - sx.floor(), sy - sy.floor());
+    let clamp_x = |x: f32| (x.max(0.0) as usize).min(image.width - 1);
+    let clamp_y = |y: f32| (y.max(0.0) as usize).min(image.height - 1);
+    let (x0, x1) = (clamp_x(sx.floor()), clamp_x(sx.floor() + 1.0));
+    let (y0, y1) = (clamp_y(sy.floor()), clamp_y(sy.floor() + 1.0));
+
+    let mut sum = [0.0f32; 4];
+    for (x, y, weight) in [
+        (x0, y0, (1.0 - fx) * (1.0 - fy)),
+        (x1, y0, fx * (1.0 - fy)),
+        (x0, y1, (1.0 - fx) * fy),
+        (x1, y1, fx * fy),
+    ] {
+        let pixel = image.pixels[y * image.width + x];
+        let alpha = color::alpha(pixel) as f32 * weight;
+        let (r, g, b) = color::channels(pixel);
+        sum[0] += r as f32 * alpha;
+        sum[1] += g as f32 * alpha;
+        sum[2] += b as f32 * alpha;
+        sum[3] += alpha;
+    }
+    if sum[3] <= 0.0 {
+        return color::rgba(0, 0, 0, 0);
+    }
+    let channel = |value: f32| (value / sum[3]).round().clamp(0.0, 255.0) as u8;
+    color::rgba(
+        channel(sum[0]),
+        channel(sum[1]),
+        channel(sum[2]),
+        sum[3].round().clamp(0.0, 255.0) as u8,
+    )
+}
@@ -0,0 +1,42 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use nalgebra::Vector2;
+
+use crate::Canvas;
+
+impl Canvas {
+    /// Rectangle with rounded corners. `radii` go clockwise from the top-left
+    /// corner and are clamped to half the shorter side. Stroke placement and
+    /// `only_stroke` work like `rect`.
+    pub fn rounded_rect(
+        &mut self,
+        pos: Vector2<isize>,
+        width: usize,
+        height: usize,
+        radii: [usize; 4],
+        only_stroke: bool,
+    ) {
+        let origin = pos.cast::<f32>();
+        let size = Vector2::new(width as f32, height as f32);
+        let max_radius = size.min() / 2.0;
+        let radii = radii.map(|r| (r as f32).min(max_radius));
+
+        // corner centers and the angle each corner arc starts at
+        let corners = [
+            (Vector2::new(radii[0], radii[0]), PI),
+            (Vector2::new(size.x - radii[1], radii[1]), PI + FRAC_PI_2),
+            (Vector2::new(size.x - radii[2], size.y - radii[2]), 0.0),
+            (Vector2::new(radii[3], size.y - radii[3]), FRAC_PI_2),
+        ];
+        let mut contour = Vec::new();
+        for (&(center, start), radius) in corners.iter().zip(radii) {
+            let center = origin + center;
+            if radius > 0.0 {
+                contour.extend(self.arc_points(center, Vector2::repeat(radius), start, FRAC_PI_2));
+            } else {
+                contour.push(self.transform_point(center));
+            }
+        }
+        self.paint_outline(&[contour], only_stroke);
+    }
+}
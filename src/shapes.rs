@@ -1,38 +1,32 @@
 use nalgebra::Vector2;
 
-use crate::{Pixel, Stroke, StrokeType};
+use crate::{Pixel, Rect, Stroke, StrokeType};
 
 pub trait Shape {
-    fn is_in_shape(&self, pos: Vector2<usize>) -> Option<Pixel>;
-    fn set_pos(&mut self, pos: Vector2<usize>);
+    fn is_in_shape(&self, pos: Vector2<isize>) -> Option<Pixel>;
+    fn set_pos(&mut self, pos: Vector2<isize>);
+    /// Pixels outside this rect are never in the shape. `None` means the
+    /// shape may cover any pixel.
+    fn bounding_box(&self) -> Option<Rect> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Circle {
-    pub center: Vector2<usize>,
+    pub center: Vector2<isize>,
     pub radius: usize,
     pub fill: Option<Pixel>,
     pub stroke: Stroke,
 }
 
 impl Shape for Circle {
-    fn is_in_shape(&self, pos: Vector2<usize>) -> Option<Pixel> {
+    fn is_in_shape(&self, pos: Vector2<isize>) -> Option<Pixel> {
         let (inner_rad, outer_rad) = match self.stroke.stroke_type {
-            StrokeType::Inner => (
-                if self.radius < self.stroke.width {
-                    0
-                } else {
-                    self.radius - self.stroke.width
-                },
-                self.radius,
-            ),
+            StrokeType::Inner => (self.radius.saturating_sub(self.stroke.width), self.radius),
             StrokeType::Outer => (self.radius, self.radius + self.stroke.width),
             StrokeType::Center => (
-                if self.radius < self.stroke.width / 2 {
-                    0
-                } else {
-                    self.radius - self.stroke.width / 2
-                },
+                self.radius.saturating_sub(self.stroke.width / 2),
                 self.radius + self.stroke.width / 2,
             ),
         };
@@ -42,8 +36,7 @@ impl Shape for Circle {
         let (inner_sq, outer_sq) = (inner_sq as isize, outer_sq as isize);
 
         // distance squared from center to pos
-        let dist_sq = (self.center.x as isize - pos.x as isize).pow(2)
-            + (self.center.y as isize - pos.y as isize).pow(2);
+        let dist_sq = (self.center.x - pos.x).pow(2) + (self.center.y - pos.y).pow(2);
         if dist_sq < inner_sq {
             return self.fill;
         }
@@ -53,13 +46,22 @@ impl Shape for Circle {
 
         None
     }
-    fn set_pos(&mut self, center: Vector2<usize>) {
+    fn set_pos(&mut self, center: Vector2<isize>) {
         self.center = center;
     }
+    fn bounding_box(&self) -> Option<Rect> {
+        let reach = (self.radius + self.stroke.width) as isize;
+        Some(Rect::new(
+            self.center.x - reach,
+            self.center.y - reach,
+            reach as usize * 2 + 1,
+            reach as usize * 2 + 1,
+        ))
+    }
 }
 
 impl Circle {
-    pub fn new(center: Vector2<usize>, radius: usize, fill: Option<Pixel>, stroke: Stroke) -> Self {
+    pub fn new(center: Vector2<isize>, radius: usize, fill: Option<Pixel>, stroke: Stroke) -> Self {
         Circle {
             center,
             radius,
@@ -68,3 +70,81 @@ impl Circle {
         }
     }
 }
+
+/// Rounded rectangle; `radii` go clockwise from the top-left corner.
+#[derive(Debug, Clone)]
+pub struct RoundedRect {
+    pub pos: Vector2<isize>,
+    pub width: usize,
+    pub height: usize,
+    pub radii: [usize; 4],
+    pub fill: Option<Pixel>,
+    pub stroke: Stroke,
+}
+
+impl Shape for RoundedRect {
+    fn is_in_shape(&self, pos: Vector2<isize>) -> Option<Pixel> {
+        let half = Vector2::new(self.width as f32, self.height as f32) / 2.0;
+        let center = self.pos.cast::<f32>() + half;
+        let p = pos.cast::<f32>().add_scalar(0.5) - center;
+
+        let corner = match (p.x >= 0.0, p.y >= 0.0) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (true, true) => 2,
+            (false, true) => 3,
+        };
+        let radius = (self.radii[corner] as f32).min(half.min());
+
+        // signed distance to the outline, negative inside
+        let q = p.abs() - half.add_scalar(-radius);
+        let distance = q.x.max(q.y).min(0.0) + q.sup(&Vector2::zeros()).norm() - radius;
+
+        let width = self.stroke.width as f32;
+        let (band_from, band_to) = match self.stroke.stroke_type {
+            StrokeType::Inner => (-width, 0.0),
+            StrokeType::Outer => (0.0, width),
+            StrokeType::Center => (-width / 2.0, width / 2.0),
+        };
+        if distance < band_from {
+            return self.fill;
+        }
+        if distance < band_to {
+            return Some(self.stroke.color);
+        }
+
+        None
+    }
+    fn set_pos(&mut self, pos: Vector2<isize>) {
+        self.pos = pos;
+    }
+    fn bounding_box(&self) -> Option<Rect> {
+        let reach = self.stroke.width;
+        Some(Rect::new(
+            self.pos.x - reach as isize,
+            self.pos.y - reach as isize,
+            self.width + reach * 2,
+            self.height + reach * 2,
+        ))
+    }
+}
+
+impl RoundedRect {
+    pub fn new(
+        pos: Vector2<isize>,
+        width: usize,
+        height: usize,
+        radii: [usize; 4],
+        fill: Option<Pixel>,
+        stroke: Stroke,
+    ) -> Self {
+        RoundedRect {
+            pos,
+            width,
+            height,
+            radii,
+            fill,
+            stroke,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Sprite sheets and frame animations. A `SpriteSheet` keeps one image and
+//! the rectangles of its frames; an `Animation` picks which frame to show as
+//! time passes.
+
+use std::collections::HashMap;
+
+use nalgebra::Vector2;
+
+use crate::{BlitOptions, Canvas, Image, Rect};
+
+pub struct SpriteSheet {
+    pub image: Image,
+    frames: Vec<Rect>,
+    names: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    /// A sheet with no frames; add them with `add_frame`.
+    pub fn new(image: Image) -> Self {
+        SpriteSheet {
+            image,
+            frames: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Slices `image` into `frame_width` x `frame_height` cells, numbered
+    /// left to right, top to bottom. Partial cells at the edges are left out.
+    pub fn grid(image: Image, frame_width: usize, frame_height: usize) -> Self {
+        let mut sheet = SpriteSheet::new(image);
+        if frame_width == 0 || frame_height == 0 {
+            return sheet;
+        }
+        let (columns, rows) = (
+            sheet.image.width / frame_width,
+            sheet.image.height / frame_height,
+        );
+        for row in 0..rows {
+            for column in 0..columns {
+                sheet.frames.push(Rect::new(
+                    (column * frame_width) as isize,
+                    (row * frame_height) as isize,
+                    frame_width,
+                    frame_height,
+                ));
+            }
+        }
+        sheet
+    }
+
+    /// Adds a frame and returns its index. Naming an existing name again
+    /// points the name at the new frame.
+    pub fn add_frame(&mut self, name: &str, rect: Rect) -> usize {
+        let index = self.frames.len();
+        self.frames.push(rect);
+        self.names.insert(name.to_string(), index);
+        index
+    }
+
+    /// Names an existing frame, e.g. one made by `grid`.
+    pub fn name_frame(&mut self, name: &str, index: usize) {
+        if index < self.frames.len() {
+            self.names.insert(name.to_string(), index);
+        }
+    }
+
+    pub fn frame(&self, index: usize) -> Option<Rect> {
+        self.frames.get(index).copied()
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    pub fn named(&self, name: &str) -> Option<Rect> {
+        self.index_of(name).and_then(|index| self.frame(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Copies a frame out into its own image.
+    pub fn frame_image(&self, index: usize) -> Option<Image> {
+        let rect =
+            self.frame(index)?
+                .intersect(&Rect::new(0, 0, self.image.width, self.image.height));
+        let mut image = Image::new(rect.width, rect.height);
+        for y in 0..rect.height {
+            let start = (rect.y as usize + y) * self.image.width + rect.x as usize;
+            image.pixels[y * rect.width..(y + 1) * rect.width]
+                .copy_from_slice(&self.image.pixels[start..start + rect.width]);
+        }
+        Some(image)
+    }
+}
+
+/// Steps through sprite sheet frames at a fixed rate, driven by the frame
+/// time passed to `update` (usually `Screen::delta_time`).
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<usize>,
+    frame_duration: f64,
+    elapsed: f64,
+    looping: bool,
+}
+
+impl Animation {
+    /// Shows `frames` (sheet indices) in order, `fps` frames per second,
+    /// looping.
+    pub fn new(frames: Vec<usize>, fps: f64) -> Self {
+        Animation {
+            frames,
+            frame_duration: if fps > 0.0 { 1.0 / fps } else { f64::INFINITY },
+            elapsed: 0.0,
+            looping: true,
+        }
+    }
+
+    /// Stops on the last frame instead of starting over.
+    pub fn once(mut self) -> Self {
+        self.looping = false;
+        self
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        self.elapsed += delta_time.max(0.0);
+        let total = self.frame_duration * self.frames.len() as f64;
+        if self.looping && total.is_finite() && total > 0.0 {
+            self.elapsed %= total;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    fn position(&self) -> usize {
+        let step = (self.elapsed / self.frame_duration) as usize;
+        step.min(self.frames.len().saturating_sub(1))
+    }
+
+    /// Sheet index of the frame to show, `None` for an empty animation.
+    pub fn current_frame(&self) -> Option<usize> {
+        self.frames.get(self.position()).copied()
+    }
+
+    /// Whether a non-looping animation has reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.elapsed >= self.frame_duration * self.frames.len() as f64
+    }
+}
+
+impl Canvas {
+    /// Blits frame `index` of `sheet` with its top-left corner at `pos`.
+    pub fn draw_sprite(
+        &mut self,
+        sheet: &SpriteSheet,
+        index: usize,
+        pos: Vector2<isize>,
+        options: &BlitOptions,
+    ) {
+        if let Some(rect) = sheet.frame(index) {
+            self.blit(&sheet.image, rect, pos, options);
+        }
+    }
+
+    /// Draws the current frame of `animation`.
+    pub fn draw_animation(
+        &mut self,
+        sheet: &SpriteSheet,
+        animation: &Animation,
+        pos: Vector2<isize>,
+        options: &BlitOptions,
+    ) {
+        if let Some(index) = animation.current_frame() {
+            self.draw_sprite(sheet, index, pos, options);
+        }
+    }
+}
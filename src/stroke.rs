@@ -0,0 +1,223 @@
+//! Thick strokes as geometry: every segment, join and cap becomes a polygon
+//! and the pieces are filled together with the non-zero rule, so overlapping
+//! parts are painted once.
+
+use std::f32::consts::TAU;
+
+use nalgebra::Vector2;
+
+use crate::{Canvas, LineCap, LineJoin, Stroke, raster};
+
+impl Canvas {
+    /// Open path through `points`, drawn with the current stroke and its
+    /// joins and caps. Points are in pixel-edge coordinates like `polygon`.
+    pub fn polyline(&mut self, points: &[Vector2<f32>]) {
+        let points: Vec<_> = points.iter().map(|&p| self.transform_point(p)).collect();
+        let width = self.stroke.width as f32 * self.transform_scale();
+        self.stroke_path(&points, width, 0.0);
+    }
+
+    /// Whether a stroke `width` pixels wide is drawn as a hairline rather
+    /// than as geometry.
+    pub(crate) fn is_hairline(&self, width: f32) -> bool {
+        if self.antialias {
+            width <= 1.0
+        } else {
+            width.round() <= 1.0
+        }
+    }
+
+    /// Strokes a screen-space path given in pixel-edge coordinates, starting
+    /// `dash_start` local units into the dash pattern.
+    pub(crate) fn stroke_path(&mut self, points: &[Vector2<f32>], width: f32, dash_start: f32) {
+        // like `line`, zero width still draws a hairline
+        if points.is_empty() {
+            return;
+        }
+        let scale = match self.transform_scale() {
+            s if s > 0.0 => s,
+            _ => 1.0,
+        };
+        if self.is_hairline(width) {
+            let mut dash_start = dash_start;
+            for segment in points.windows(2) {
+                // line_at works on pixel centers
+                let (start, end) = (segment[0].add_scalar(-0.5), segment[1].add_scalar(-0.5));
+                self.line_at(start, end, width, dash_start);
+                dash_start += (end - start).norm() / scale;
+            }
+            return;
+        }
+
+        let contours = if self.stroke.is_dashed() {
+            let mut contours = Vec::new();
+            // points of the dash being built, which may span several segments
+            let mut dash: Vec<Vector2<f32>> = Vec::new();
+            let mut along = dash_start;
+            for segment in points.windows(2) {
+                let (start, end) = (segment[0], segment[1]);
+                let length = (end - start).norm() / scale;
+                let point_at = |distance: f32| {
+                    let t = if length > 0.0 {
+                        (distance - along) / length
+                    } else {
+                        0.0
+                    };
+                    start + (end - start) * t
+                };
+                for (from, to) in self.stroke.dash_runs(along, along + length) {
+                    if from > along || dash.is_empty() {
+                        if !dash.is_empty() {
+                            contours.extend(outline(&dash, width, &self.stroke));
+                        }
+                        dash = vec![point_at(from)];
+                    }
+                    dash.push(point_at(to));
+                    if to < along + length {
+                        contours.extend(outline(&dash, width, &self.stroke));
+                        dash.clear();
+                    }
+                }
+                along += length;
+            }
+            if !dash.is_empty() {
+                contours.extend(outline(&dash, width, &self.stroke));
+            }
+            contours
+        } else {
+            outline(points, width, &self.stroke)
+        };
+
+        let (clip, antialias, color) = (self.clip, self.antialias, self.stroke.color);
+        raster::fill_polygon(
+            &contours,
+            clip,
+            antialias,
+            |winding| winding != 0,
+            |x, y, coverage| self.plot(x, y, color, coverage),
+        );
+    }
+}
+
+/// Polygons covering an open path stroked `width` wide, all wound the same
+/// way so the non-zero rule fills their union.
+fn outline(points: &[Vector2<f32>], width: f32, stroke: &Stroke) -> Vec<Vec<Vector2<f32>>> {
+    let half = width / 2.0;
+    let mut path: Vec<Vector2<f32>> = Vec::with_capacity(points.len());
+    for &point in points {
+        if path
+            .last()
+            .is_none_or(|last: &Vector2<f32>| (point - last).norm() > 1e-4)
+        {
+            path.push(point);
+        }
+    }
+    let mut contours = Vec::new();
+    let Some(&first) = path.first() else {
+        return contours;
+    };
+
+    if path.len() == 1 {
+        // a zero-length path is all cap
+        match stroke.line_cap {
+            LineCap::Butt => {}
+            LineCap::Round => contours.push(circle(first, half)),
+            LineCap::Square => {
+                let (x, y) = (Vector2::new(half, 0.0), Vector2::new(0.0, half));
+                contours.push(vec![
+                    first - x - y,
+                    first + x - y,
+                    first + x + y,
+                    first - x + y,
+                ]);
+            }
+        }
+        return orient(contours);
+    }
+
+    let direction = |a: Vector2<f32>, b: Vector2<f32>| (b - a).normalize();
+    let normal = |d: Vector2<f32>| Vector2::new(-d.y, d.x) * half;
+
+    for segment in path.windows(2) {
+        let n = normal(direction(segment[0], segment[1]));
+        contours.push(vec![
+            segment[0] + n,
+            segment[1] + n,
+            segment[1] - n,
+            segment[0] - n,
+        ]);
+    }
+
+    for corner in path.windows(3) {
+        let (d0, d1) = (
+            direction(corner[0], corner[1]),
+            direction(corner[1], corner[2]),
+        );
+        let p = corner[1];
+        let turn = d0.x * d1.y - d0.y * d1.x;
+        if turn.abs() < 1e-6 && d0.dot(&d1) > 0.0 {
+            continue;
+        }
+        // the join fills the gap on the outside of the turn
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let (n0, n1) = (normal(d0) * side, normal(d1) * side);
+        match stroke.line_join {
+            LineJoin::Round => contours.push(circle(p, half)),
+            LineJoin::Bevel => contours.push(vec![p, p + n0, p + n1]),
+            LineJoin::Miter => {
+                let bisector = n0 + n1;
+                // ratio of miter length to stroke width is 1 / cos(turn / 2)
+                let cos_half = (bisector.norm() / 2.0) / half;
+                if cos_half > 1e-6 && 1.0 / cos_half <= stroke.miter_limit {
+                    let tip = p + bisector.normalize() * (half / cos_half);
+                    contours.push(vec![p, p + n0, tip, p + n1]);
+                } else {
+                    contours.push(vec![p, p + n0, p + n1]);
+                }
+            }
+        }
+    }
+
+    let last = path[path.len() - 1];
+    let caps = [
+        (first, direction(path[1], first)),
+        (last, direction(path[path.len() - 2], last)),
+    ];
+    for (end, outward) in caps {
+        let n = normal(outward);
+        match stroke.line_cap {
+            LineCap::Butt => {}
+            LineCap::Round => contours.push(circle(end, half)),
+            LineCap::Square => {
+                let reach = outward * half;
+                contours.push(vec![end + n, end + n + reach, end - n + reach, end - n]);
+            }
+        }
+    }
+    orient(contours)
+}
+
+fn circle(center: Vector2<f32>, radius: f32) -> Vec<Vector2<f32>> {
+    let segments = (7.0 * radius.sqrt()).ceil().max(16.0) as usize;
+    (0..segments)
+        .map(|i| {
+            let angle = TAU * i as f32 / segments as f32;
+            center + Vector2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Reverses contours as needed so that all of them wind the same way.
+fn orient(mut contours: Vec<Vec<Vector2<f32>>>) -> Vec<Vec<Vector2<f32>>> {
+    for contour in &mut contours {
+        let area: f32 = contour
+            .iter()
+            .zip(contour.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        if area < 0.0 {
+            contour.reverse();
+        }
+    }
+    contours
+}
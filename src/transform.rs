@@ -0,0 +1,111 @@
+use nalgebra::{Isometry2, Matrix3, Point2, Vector2};
+
+use crate::{Canvas, Paint, raster};
+
+/// Where the current transform sends a primitive. Pure integer translations
+/// keep the pixel-exact fast paths; everything else goes through geometry.
+pub(crate) enum Placement {
+    Offset(Vector2<isize>),
+    General,
+}
+
+impl Canvas {
+    /// Saves the current transform; restore it with `pop_transform`.
+    pub fn push_transform(&mut self) {
+        self.transform_stack.push(self.transform);
+    }
+
+    pub fn pop_transform(&mut self) {
+        if let Some(transform) = self.transform_stack.pop() {
+            self.transform = transform;
+        }
+    }
+
+    pub fn reset_transform(&mut self) {
+        self.transform = Matrix3::identity();
+    }
+
+    pub fn translate(&mut self, x: f32, y: f32) {
+        self.transform *= Matrix3::new_translation(&Vector2::new(x, y));
+    }
+
+    /// Rotates subsequent drawing by `angle` radians, clockwise on screen.
+    pub fn rotate(&mut self, angle: f32) {
+        self.transform *= Matrix3::new_rotation(angle);
+    }
+
+    pub fn scale(&mut self, x: f32, y: f32) {
+        self.transform *= Matrix3::new_nonuniform_scaling(&Vector2::new(x, y));
+    }
+
+    pub fn apply_transform(&mut self, matrix: &Matrix3<f32>) {
+        self.transform *= matrix;
+    }
+
+    pub fn apply_isometry(&mut self, isometry: &Isometry2<f32>) {
+        self.apply_transform(&isometry.to_homogeneous());
+    }
+
+    pub fn get_transform(&self) -> Matrix3<f32> {
+        self.transform
+    }
+
+    pub(crate) fn placement(&self) -> Placement {
+        let m = &self.transform;
+        let (tx, ty) = (m[(0, 2)], m[(1, 2)]);
+        let translation_only = m[(0, 0)] == 1.0
+            && m[(1, 1)] == 1.0
+            && m[(0, 1)] == 0.0
+            && m[(1, 0)] == 0.0
+            && m[(2, 0)] == 0.0
+            && m[(2, 1)] == 0.0;
+        if translation_only && tx.fract() == 0.0 && ty.fract() == 0.0 {
+            Placement::Offset(Vector2::new(tx as isize, ty as isize))
+        } else {
+            Placement::General
+        }
+    }
+
+    pub(crate) fn transform_point(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.transform.transform_point(&Point2::from(point)).coords
+    }
+
+    /// Average factor by which the transform scales lengths.
+    pub(crate) fn transform_scale(&self) -> f32 {
+        let m = &self.transform;
+        (m[(0, 0)] * m[(1, 1)] - m[(0, 1)] * m[(1, 0)]).abs().sqrt()
+    }
+
+    /// Fills contours given in screen space with the even-odd rule.
+    pub(crate) fn fill_contours(&mut self, contours: &[Vec<Vector2<f32>>], paint: &Paint) {
+        self.fill_contours_masked(contours, paint, |_| true);
+    }
+
+    /// Like `fill_contours`, but only where `keep` accepts the pixel center
+    /// mapped back to local space.
+    pub(crate) fn fill_contours_masked(
+        &mut self,
+        contours: &[Vec<Vector2<f32>>],
+        paint: &Paint,
+        keep: impl Fn(Vector2<f32>) -> bool,
+    ) {
+        let (clip, antialias) = (self.clip, self.antialias);
+        let paint = self.sampler(paint);
+        let to_local = self
+            .transform
+            .try_inverse()
+            .unwrap_or_else(Matrix3::identity);
+        raster::fill_polygon(
+            contours,
+            clip,
+            antialias,
+            |winding| winding % 2 != 0,
+            |x, y, coverage| {
+                let center = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if keep(to_local.transform_point(&center).coords) {
+                    self.plot(x, y, paint.color_at(x, y), coverage);
+                }
+            },
+        );
+    }
+}